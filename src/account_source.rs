@@ -0,0 +1,368 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+
+use async_trait::async_trait;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_filter::RpcFilterType,
+    rpc_response::{Response, RpcKeyedAccount, RpcResponseContext},
+};
+use solana_sdk::{
+    account::Account,
+    hash::{hash, Hash},
+    instruction::CompiledInstruction,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    UiTransactionEncoding,
+};
+
+// One getSignaturesForAddress entry
+#[derive(Clone, Debug)]
+pub struct SignatureInfo {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+}
+
+// A confirmed transaction reduced to what instruction decoding needs
+#[derive(Clone, Debug)]
+pub struct HistoricalTransaction {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    // static keys followed by the writable and then readonly lookup table addresses, so
+    // compiled instruction indexes resolve against it directly
+    pub account_keys: Vec<Pubkey>,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+impl HistoricalTransaction {
+    fn from_encoded(
+        signature: Signature,
+        encoded: EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<Self, String> {
+        let transaction = encoded
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| format!("transaction {} could not be decoded", signature))?;
+        let mut account_keys = transaction.message.static_account_keys().to_vec();
+        let meta = encoded.transaction.meta;
+        if let Some(OptionSerializer::Some(loaded)) =
+            meta.as_ref().map(|meta| meta.loaded_addresses.as_ref())
+        {
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                account_keys.push(Pubkey::from_str(address).map_err(|e| {
+                    format!(
+                        "transaction {} loaded address {}: {}",
+                        signature, address, e
+                    )
+                })?);
+            }
+        }
+        Ok(HistoricalTransaction {
+            signature,
+            slot: encoded.slot,
+            block_time: encoded.block_time,
+            failed: meta.is_some_and(|meta| meta.err.is_some()),
+            account_keys,
+            instructions: transaction.message.instructions().to_vec(),
+        })
+    }
+}
+
+// Everything the handlers need from the chain. The rpc client implements it for real
+// deployments and InMemoryAccountSource serves accounts without a network.
+#[async_trait]
+pub trait AccountSource: Send + Sync {
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Response<Option<Account>>>;
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> ClientResult<Response<Vec<Option<Account>>>>;
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, Account)>>;
+
+    async fn get_slot(&self) -> ClientResult<u64>;
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+    // Newest first, starting after `before` when given
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<SignatureInfo>>;
+
+    async fn get_transaction(&self, signature: &Signature) -> ClientResult<HistoricalTransaction>;
+}
+
+#[async_trait]
+impl AccountSource for RpcClient {
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Response<Option<Account>>> {
+        self.get_account_with_commitment(address, self.commitment())
+            .await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> ClientResult<Response<Vec<Option<Account>>>> {
+        self.get_multiple_accounts_with_commitment(addresses, self.commitment())
+            .await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.get_program_accounts_with_config(program_id, config)
+            .await
+    }
+
+    async fn get_slot(&self) -> ClientResult<u64> {
+        RpcClient::get_slot(self).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        RpcClient::get_latest_blockhash(self).await
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<SignatureInfo>> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until: None,
+            limit: Some(limit),
+            commitment: Some(self.commitment()),
+        };
+        let statuses = self
+            .get_signatures_for_address_with_config(address, config)
+            .await?;
+        let mut signatures = Vec::with_capacity(statuses.len());
+        for status in statuses {
+            let signature = Signature::from_str(&status.signature).map_err(|e| {
+                ClientErrorKind::Custom(format!("invalid signature {}: {}", status.signature, e))
+            })?;
+            signatures.push(SignatureInfo {
+                signature,
+                slot: status.slot,
+                block_time: status.block_time,
+                failed: status.err.is_some(),
+            });
+        }
+        Ok(signatures)
+    }
+
+    async fn get_transaction(&self, signature: &Signature) -> ClientResult<HistoricalTransaction> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let encoded = self.get_transaction_with_config(signature, config).await?;
+        Ok(HistoricalTransaction::from_encoded(*signature, encoded)
+            .map_err(ClientErrorKind::Custom)?)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryAccountSource {
+    accounts: RwLock<HashMap<Pubkey, Account>>,
+    // oldest first, like a ledger
+    transactions: RwLock<Vec<HistoricalTransaction>>,
+    slot: AtomicU64,
+}
+
+impl InMemoryAccountSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Load every `*.json` file in a directory, in the same format as
+    // `solana account <ADDRESS> --output json` and `solana-test-validator --account-dir`
+    pub fn from_account_dir(dir: &Path) -> Result<Self, String> {
+        let source = Self::new();
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let contents =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let keyed_account: RpcKeyedAccount = serde_json::from_str(&contents)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            let address = Pubkey::from_str(&keyed_account.pubkey)
+                .map_err(|e| format!("{}: invalid pubkey: {}", path.display(), e))?;
+            let account = keyed_account
+                .account
+                .decode::<Account>()
+                .ok_or_else(|| format!("{}: undecodable account data", path.display()))?;
+            source.insert(address, account);
+        }
+        Ok(source)
+    }
+
+    pub fn insert(&self, address: Pubkey, account: Account) {
+        self.accounts.write().unwrap().insert(address, account);
+        // every write lands in a new slot, like it would on chain
+        self.slot.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn insert_transaction(&self, transaction: HistoricalTransaction) {
+        self.transactions.write().unwrap().push(transaction);
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn context(&self) -> RpcResponseContext {
+        RpcResponseContext::new(self.slot.load(Ordering::SeqCst))
+    }
+}
+
+// Same semantics the rpc node applies to getProgramAccounts filters
+fn filter_allows(filter: &RpcFilterType, data: &[u8]) -> bool {
+    match filter {
+        RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+        RpcFilterType::Memcmp(compare) => compare.bytes_match(data),
+        // no token accounts are ever owned by the satellite program
+        RpcFilterType::TokenAccountState => false,
+    }
+}
+
+#[async_trait]
+impl AccountSource for InMemoryAccountSource {
+    async fn get_account(&self, address: &Pubkey) -> ClientResult<Response<Option<Account>>> {
+        let accounts = self.accounts.read().unwrap();
+        Ok(Response {
+            context: self.context(),
+            value: accounts.get(address).cloned(),
+        })
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> ClientResult<Response<Vec<Option<Account>>>> {
+        let accounts = self.accounts.read().unwrap();
+        Ok(Response {
+            context: self.context(),
+            value: addresses
+                .iter()
+                .map(|address| accounts.get(address).cloned())
+                .collect(),
+        })
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        for filter in &filters {
+            filter
+                .verify()
+                .map_err(|e| ClientErrorKind::Custom(format!("invalid filter: {}", e)))?;
+        }
+        let accounts = self.accounts.read().unwrap();
+        Ok(accounts
+            .iter()
+            .filter(|(_, account)| account.owner == *program_id)
+            .filter(|(_, account)| {
+                filters
+                    .iter()
+                    .all(|filter| filter_allows(filter, &account.data))
+            })
+            .map(|(address, account)| (*address, account.clone()))
+            .collect())
+    }
+
+    async fn get_slot(&self) -> ClientResult<u64> {
+        Ok(self.slot.load(Ordering::SeqCst))
+    }
+
+    // a stable stand-in that changes whenever the slot does
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        Ok(hash(&self.slot.load(Ordering::SeqCst).to_le_bytes()))
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<SignatureInfo>> {
+        let transactions = self.transactions.read().unwrap();
+        let mut newest_first = transactions
+            .iter()
+            .rev()
+            .filter(|transaction| transaction.account_keys.contains(address));
+        if let Some(before) = before {
+            // an unknown cursor has nothing before it
+            newest_first.find(|transaction| transaction.signature == before);
+        }
+        Ok(newest_first
+            .take(limit)
+            .map(|transaction| SignatureInfo {
+                signature: transaction.signature,
+                slot: transaction.slot,
+                block_time: transaction.block_time,
+                failed: transaction.failed,
+            })
+            .collect())
+    }
+
+    async fn get_transaction(&self, signature: &Signature) -> ClientResult<HistoricalTransaction> {
+        let transactions = self.transactions.read().unwrap();
+        transactions
+            .iter()
+            .find(|transaction| transaction.signature == *signature)
+            .cloned()
+            .ok_or_else(|| {
+                ClientErrorKind::Custom(format!("transaction {} not found", signature)).into()
+            })
+    }
+}
@@ -0,0 +1,466 @@
+use std::{env, fmt, fs, path::PathBuf, str::FromStr, time::Duration};
+
+use shuttle_runtime::SecretStore;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use url::Url;
+
+// Defaults match the original hard-coded devnet deployment
+pub const DEFAULT_CLUSTER: &str = "devnet";
+pub const DEFAULT_PROGRAM_ID: &str = "FZQmSamSJdtB9JKxbUH82ZdRQ2UcqqBPGbyce2ZdfviN";
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+// roughly the time devnet takes to finalize a slot; 0 turns the satellite cache off
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 10;
+pub const DEFAULT_FRUIT_DB_PATH: &str = "fruits.db";
+pub const DEFAULT_CONFIG_FILE: &str = "Config.toml";
+
+// Environment variable (or Shuttle secret) holding the path of an optional TOML file
+pub const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountSourceKind {
+    // talk to the configured rpc node
+    Rpc,
+    // serve accounts loaded from `account_dir`, no network needed
+    Memory,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FruitStoreKind {
+    // the sample catalog, lost on restart
+    Memory,
+    // persisted in the SQLite database at `fruit_db_path`
+    Sqlite,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub cluster: String,
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub commitment: CommitmentConfig,
+    pub program_id: Pubkey,
+    pub request_timeout: Duration,
+    pub account_source: AccountSourceKind,
+    pub account_dir: Option<PathBuf>,
+    pub cache_ttl: Duration,
+    pub fruit_store: FruitStoreKind,
+    pub fruit_db_path: PathBuf,
+}
+
+// Unvalidated values as they come out of a single source; later sources override earlier ones
+#[derive(Debug, Default)]
+pub struct RawConfig {
+    pub cluster: Option<String>,
+    pub rpc_url: Option<String>,
+    pub ws_url: Option<String>,
+    pub commitment: Option<String>,
+    pub program_id: Option<String>,
+    pub request_timeout_secs: Option<String>,
+    pub account_source: Option<String>,
+    pub account_dir: Option<String>,
+    pub cache_ttl_secs: Option<String>,
+    pub fruit_store: Option<String>,
+    pub fruit_db_path: Option<String>,
+}
+
+// Shape of the optional TOML file, e.g.
+//   cluster = "localnet"
+//   program_id = "FZQm..."
+//   request_timeout_secs = 10
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub cluster: Option<String>,
+    pub rpc_url: Option<String>,
+    pub ws_url: Option<String>,
+    pub commitment: Option<String>,
+    pub program_id: Option<String>,
+    pub request_timeout_secs: Option<u64>,
+    pub account_source: Option<String>,
+    pub account_dir: Option<String>,
+    pub cache_ttl_secs: Option<u64>,
+    pub fruit_store: Option<String>,
+    pub fruit_db_path: Option<String>,
+}
+
+impl From<FileConfig> for RawConfig {
+    fn from(file: FileConfig) -> Self {
+        RawConfig {
+            cluster: file.cluster,
+            rpc_url: file.rpc_url,
+            ws_url: file.ws_url,
+            commitment: file.commitment,
+            program_id: file.program_id,
+            request_timeout_secs: file.request_timeout_secs.map(|secs| secs.to_string()),
+            account_source: file.account_source,
+            account_dir: file.account_dir,
+            cache_ttl_secs: file.cache_ttl_secs.map(|secs| secs.to_string()),
+            fruit_store: file.fruit_store,
+            fruit_db_path: file.fruit_db_path,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    ReadFile { path: PathBuf, reason: String },
+    ParseFile { path: PathBuf, reason: String },
+    UnknownCluster(String),
+    InvalidUrl { key: &'static str, value: String },
+    // rpc_url is on a port whose websocket counterpart can't be guessed
+    MissingWsUrl(String),
+    InvalidCommitment(String),
+    InvalidProgramId { value: String, reason: String },
+    InvalidTimeout(String),
+    InvalidCacheTtl(String),
+    InvalidAccountSource(String),
+    MissingAccountDir,
+    InvalidFruitStore(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ReadFile { path, reason } => {
+                write!(
+                    f,
+                    "could not read config file {}: {}",
+                    path.display(),
+                    reason
+                )
+            }
+            ConfigError::ParseFile { path, reason } => {
+                write!(f, "invalid config file {}: {}", path.display(), reason)
+            }
+            ConfigError::UnknownCluster(cluster) => write!(
+                f,
+                "unknown cluster '{}', expected one of localnet, devnet, testnet, mainnet-beta",
+                cluster
+            ),
+            ConfigError::InvalidUrl { key, value } => write!(
+                f,
+                "{} '{}' must start with {}",
+                key,
+                value,
+                if *key == "ws_url" {
+                    "ws:// or wss://"
+                } else {
+                    "http:// or https://"
+                }
+            ),
+            ConfigError::MissingWsUrl(rpc_url) => write!(
+                f,
+                "ws_url must be set: rpc_url '{}' is on a custom port, so its websocket endpoint cannot be derived",
+                rpc_url
+            ),
+            ConfigError::InvalidCommitment(value) => write!(
+                f,
+                "commitment '{}' must be one of processed, confirmed, finalized",
+                value
+            ),
+            ConfigError::InvalidProgramId { value, reason } => {
+                write!(
+                    f,
+                    "program_id '{}' is not a valid Pubkey: {}",
+                    value, reason
+                )
+            }
+            ConfigError::InvalidTimeout(value) => write!(
+                f,
+                "request_timeout_secs '{}' must be a positive number of seconds",
+                value
+            ),
+            ConfigError::InvalidCacheTtl(value) => write!(
+                f,
+                "cache_ttl_secs '{}' must be a whole number of seconds, 0 to disable caching",
+                value
+            ),
+            ConfigError::InvalidAccountSource(value) => {
+                write!(f, "account_source '{}' must be one of rpc, memory", value)
+            }
+            ConfigError::MissingAccountDir => {
+                write!(f, "account_source 'memory' requires account_dir to be set")
+            }
+            ConfigError::InvalidFruitStore(value) => {
+                write!(f, "fruit_store '{}' must be one of memory, sqlite", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl RawConfig {
+    pub fn from_toml_file(path: PathBuf) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(&path).map_err(|e| ConfigError::ReadFile {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+        toml::from_str::<FileConfig>(&contents)
+            .map(RawConfig::from)
+            .map_err(|e| ConfigError::ParseFile {
+                path,
+                reason: e.to_string(),
+            })
+    }
+
+    // Keys are looked up upper-cased, e.g. RPC_URL, PROGRAM_ID
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        RawConfig {
+            cluster: lookup("CLUSTER"),
+            rpc_url: lookup("RPC_URL"),
+            ws_url: lookup("WS_URL"),
+            commitment: lookup("COMMITMENT"),
+            program_id: lookup("PROGRAM_ID"),
+            request_timeout_secs: lookup("REQUEST_TIMEOUT_SECS"),
+            account_source: lookup("ACCOUNT_SOURCE"),
+            account_dir: lookup("ACCOUNT_DIR"),
+            cache_ttl_secs: lookup("CACHE_TTL_SECS"),
+            fruit_store: lookup("FRUIT_STORE"),
+            fruit_db_path: lookup("FRUIT_DB_PATH"),
+        }
+    }
+
+    pub fn merge(self, other: RawConfig) -> Self {
+        RawConfig {
+            cluster: other.cluster.or(self.cluster),
+            rpc_url: other.rpc_url.or(self.rpc_url),
+            ws_url: other.ws_url.or(self.ws_url),
+            commitment: other.commitment.or(self.commitment),
+            program_id: other.program_id.or(self.program_id),
+            request_timeout_secs: other.request_timeout_secs.or(self.request_timeout_secs),
+            account_source: other.account_source.or(self.account_source),
+            account_dir: other.account_dir.or(self.account_dir),
+            cache_ttl_secs: other.cache_ttl_secs.or(self.cache_ttl_secs),
+            fruit_store: other.fruit_store.or(self.fruit_store),
+            fruit_db_path: other.fruit_db_path.or(self.fruit_db_path),
+        }
+    }
+}
+
+pub fn cluster_rpc_url(cluster: &str) -> Option<&'static str> {
+    match cluster {
+        "localnet" | "localhost" => Some("http://127.0.0.1:8899"),
+        "devnet" => Some("https://api.devnet.solana.com"),
+        "testnet" => Some("https://api.testnet.solana.com"),
+        "mainnet-beta" | "mainnet" => Some("https://api.mainnet-beta.solana.com"),
+        _ => None,
+    }
+}
+
+// solana-test-validator serves pubsub on the port after its rpc one
+const LOCAL_RPC_PORT: u16 = 8899;
+const LOCAL_WS_PORT: u16 = 8900;
+
+// Same convention as the solana cli: the same host with a ws scheme for hosted clusters, and
+// the next port up for a local validator. None for any other port, which would be a guess.
+pub fn ws_url_from_rpc_url(rpc_url: &Url) -> Option<Url> {
+    let scheme = match rpc_url.scheme() {
+        "http" => "ws",
+        "https" => "wss",
+        _ => return None,
+    };
+    let mut ws_url = rpc_url.clone();
+    ws_url.set_scheme(scheme).ok()?;
+    match rpc_url.port() {
+        None => {}
+        Some(LOCAL_RPC_PORT) => ws_url.set_port(Some(LOCAL_WS_PORT)).ok()?,
+        Some(_) => return None,
+    }
+    Some(ws_url)
+}
+
+// Parses a url and checks it uses one of the given schemes
+fn parse_url(key: &'static str, value: &str, schemes: [&str; 2]) -> Result<Url, ConfigError> {
+    match Url::parse(value) {
+        Ok(url) if schemes.contains(&url.scheme()) => Ok(url),
+        _ => Err(ConfigError::InvalidUrl {
+            key,
+            value: value.to_string(),
+        }),
+    }
+}
+
+impl AppConfig {
+    // Precedence, lowest to highest: defaults, TOML file, Shuttle secrets, environment variables
+    pub fn load(secrets: &SecretStore) -> Result<Self, ConfigError> {
+        let env_config = RawConfig::from_lookup(|key| env::var(key).ok());
+        let secrets_config = RawConfig::from_lookup(|key| secrets.get(key));
+
+        let config_file = env::var(CONFIG_FILE_VAR)
+            .ok()
+            .or_else(|| secrets.get(CONFIG_FILE_VAR))
+            .map(PathBuf::from);
+        let file_config = match config_file {
+            Some(path) => RawConfig::from_toml_file(path)?,
+            None => {
+                let default_path = PathBuf::from(DEFAULT_CONFIG_FILE);
+                if default_path.exists() {
+                    RawConfig::from_toml_file(default_path)?
+                } else {
+                    RawConfig::default()
+                }
+            }
+        };
+
+        Self::from_raw(file_config.merge(secrets_config).merge(env_config))
+    }
+
+    pub fn from_raw(raw: RawConfig) -> Result<Self, ConfigError> {
+        // checked even when rpc_url overrides it, since the cluster is still logged at startup
+        let cluster = raw.cluster.unwrap_or_else(|| DEFAULT_CLUSTER.to_string());
+        let cluster_url = cluster_rpc_url(&cluster)
+            .ok_or_else(|| ConfigError::UnknownCluster(cluster.clone()))?;
+        let rpc_url = raw.rpc_url.unwrap_or_else(|| cluster_url.to_string());
+        let parsed_rpc_url = parse_url("rpc_url", &rpc_url, ["http", "https"])?;
+
+        let ws_url = match raw.ws_url {
+            Some(ws_url) => {
+                parse_url("ws_url", &ws_url, ["ws", "wss"])?;
+                ws_url
+            }
+            None => ws_url_from_rpc_url(&parsed_rpc_url)
+                .ok_or_else(|| ConfigError::MissingWsUrl(rpc_url.clone()))?
+                .to_string(),
+        };
+
+        let commitment = match raw.commitment {
+            Some(commitment) => CommitmentConfig::from_str(&commitment)
+                .map_err(|_| ConfigError::InvalidCommitment(commitment))?,
+            None => CommitmentConfig::finalized(),
+        };
+
+        let program_id_str = raw
+            .program_id
+            .unwrap_or_else(|| DEFAULT_PROGRAM_ID.to_string());
+        let program_id =
+            Pubkey::from_str(&program_id_str).map_err(|e| ConfigError::InvalidProgramId {
+                value: program_id_str.clone(),
+                reason: e.to_string(),
+            })?;
+
+        let request_timeout_secs = match raw.request_timeout_secs {
+            Some(value) => match value.parse::<u64>() {
+                Ok(secs) if secs > 0 => secs,
+                _ => return Err(ConfigError::InvalidTimeout(value)),
+            },
+            None => DEFAULT_REQUEST_TIMEOUT_SECS,
+        };
+
+        let account_source = match raw.account_source.as_deref() {
+            None | Some("rpc") => AccountSourceKind::Rpc,
+            Some("memory") => AccountSourceKind::Memory,
+            Some(other) => return Err(ConfigError::InvalidAccountSource(other.to_string())),
+        };
+        let account_dir = raw.account_dir.map(PathBuf::from);
+        if account_source == AccountSourceKind::Memory && account_dir.is_none() {
+            return Err(ConfigError::MissingAccountDir);
+        }
+
+        let cache_ttl_secs = match raw.cache_ttl_secs {
+            Some(value) => value
+                .parse::<u64>()
+                .map_err(|_| ConfigError::InvalidCacheTtl(value))?,
+            None => DEFAULT_CACHE_TTL_SECS,
+        };
+
+        let fruit_store = match raw.fruit_store.as_deref() {
+            None | Some("memory") => FruitStoreKind::Memory,
+            Some("sqlite") => FruitStoreKind::Sqlite,
+            Some(other) => return Err(ConfigError::InvalidFruitStore(other.to_string())),
+        };
+        let fruit_db_path = PathBuf::from(
+            raw.fruit_db_path
+                .unwrap_or_else(|| DEFAULT_FRUIT_DB_PATH.to_string()),
+        );
+
+        Ok(AppConfig {
+            cluster,
+            rpc_url,
+            ws_url,
+            commitment,
+            program_id,
+            request_timeout: Duration::from_secs(request_timeout_secs),
+            account_source,
+            account_dir,
+            cache_ttl: Duration::from_secs(cache_ttl_secs),
+            fruit_store,
+            fruit_db_path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_cluster_is_rejected_with_an_rpc_url() {
+        let raw = RawConfig {
+            cluster: Some("mainet".to_string()),
+            rpc_url: Some("http://127.0.0.1:8899".to_string()),
+            ..RawConfig::default()
+        };
+        assert!(matches!(
+            AppConfig::from_raw(raw),
+            Err(ConfigError::UnknownCluster(cluster)) if cluster == "mainet"
+        ));
+    }
+
+    #[test]
+    fn rpc_url_overrides_the_cluster_endpoint() {
+        let raw = RawConfig {
+            cluster: Some("localnet".to_string()),
+            rpc_url: Some("http://validator:8899".to_string()),
+            ..RawConfig::default()
+        };
+        let config = AppConfig::from_raw(raw).unwrap();
+        assert_eq!(config.cluster, "localnet");
+        assert_eq!(config.rpc_url, "http://validator:8899");
+        assert_eq!(config.ws_url, "ws://validator:8900/");
+    }
+
+    #[test]
+    fn ws_url_is_only_derived_for_known_ports() {
+        let ws_url = |rpc_url: &str, ws_url: Option<&str>| {
+            AppConfig::from_raw(RawConfig {
+                rpc_url: Some(rpc_url.to_string()),
+                ws_url: ws_url.map(str::to_string),
+                ..RawConfig::default()
+            })
+            .map(|config| config.ws_url)
+        };
+        assert_eq!(
+            ws_url("https://api.devnet.solana.com", None).unwrap(),
+            "wss://api.devnet.solana.com/"
+        );
+        assert_eq!(
+            ws_url("http://127.0.0.1:8899", None).unwrap(),
+            "ws://127.0.0.1:8900/"
+        );
+        // only the port is remapped, not a lookalike elsewhere in the url
+        assert_eq!(
+            ws_url("https://rpc.example.com/?token=ab:8899", None).unwrap(),
+            "wss://rpc.example.com/?token=ab:8899"
+        );
+        assert!(matches!(
+            ws_url("http://rpc.example.com:9000", None),
+            Err(ConfigError::MissingWsUrl(rpc_url)) if rpc_url == "http://rpc.example.com:9000"
+        ));
+        assert_eq!(
+            ws_url(
+                "http://rpc.example.com:9000",
+                Some("ws://rpc.example.com:9001")
+            )
+            .unwrap(),
+            "ws://rpc.example.com:9001"
+        );
+        assert!(matches!(
+            ws_url(
+                "https://api.devnet.solana.com",
+                Some("https://api.devnet.solana.com")
+            ),
+            Err(ConfigError::InvalidUrl { key: "ws_url", .. })
+        ));
+    }
+}
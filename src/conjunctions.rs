@@ -0,0 +1,382 @@
+use std::sync::Arc;
+
+use axum::{debug_handler, extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    decode_satellite_account, fetch_satellite, from_unix_seconds, parse_window, unix_seconds,
+    ApiError, AppState, KeplerianElements, PropagationModel, SatelliteApiResponse,
+    SatelliteListQuery, SatellitePath, SatelliteSeeds, ValidQuery,
+};
+
+pub const DEFAULT_CONJUNCTION_THRESHOLD_KM: f64 = 5.0;
+
+// coarse scan step; closest approaches are refined between neighbouring samples
+const CONJUNCTION_SCAN_STEP_SECS: f64 = 30.0;
+const CONJUNCTION_TIME_TOLERANCE_SECS: f64 = 0.01;
+
+// A satellite taking part in the screening, with the elements it is propagated from
+#[derive(Clone, Debug)]
+pub struct ScreeningObject {
+    pub address: Pubkey,
+    pub norad_id: u64,
+    pub name: String,
+    pub elements: KeplerianElements,
+}
+
+impl ScreeningObject {
+    fn new(address: Pubkey, satellite: SatelliteApiResponse) -> Result<Self, ApiError> {
+        Ok(ScreeningObject {
+            address,
+            elements: KeplerianElements::from_satellite(&satellite)?,
+            norad_id: satellite.norad_id,
+            name: satellite.name,
+        })
+    }
+
+    // km from the Earth's centre
+    fn radius_range(&self) -> (f64, f64) {
+        let elements = &self.elements;
+        (
+            elements.semi_major_axis * (1.0 - elements.eccentricity),
+            elements.semi_major_axis * (1.0 + elements.eccentricity),
+        )
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ConjunctionObject {
+    pub address: String,
+    pub norad_id: u64,
+    pub name: String,
+}
+
+impl From<&ScreeningObject> for ConjunctionObject {
+    fn from(object: &ScreeningObject) -> Self {
+        ConjunctionObject {
+            address: object.address.to_string(),
+            norad_id: object.norad_id,
+            name: object.name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Conjunction {
+    pub secondary: ConjunctionObject,
+    pub time_of_closest_approach: DateTime<Utc>,
+    // km
+    pub miss_distance: f64,
+    // km/s
+    pub relative_speed: f64,
+}
+
+struct ConjunctionScreener<'a> {
+    primary: &'a ScreeningObject,
+    model: PropagationModel,
+    threshold: f64,
+}
+
+impl ConjunctionScreener<'_> {
+    fn distance(&self, secondary: &ScreeningObject, unix_time: f64) -> f64 {
+        let primary = self.primary.elements.propagate(self.model, unix_time);
+        let secondary = secondary.elements.propagate(self.model, unix_time);
+        primary.position.sub(&secondary.position).norm()
+    }
+
+    // Orbits whose radius ranges are further apart than the threshold can never meet
+    fn shells_overlap(&self, secondary: &ScreeningObject) -> bool {
+        let (primary_min, primary_max) = self.primary.radius_range();
+        let (secondary_min, secondary_max) = secondary.radius_range();
+        primary_min <= secondary_max + self.threshold
+            && secondary_min <= primary_max + self.threshold
+    }
+
+    // golden-section search for the smallest distance within [start, end]
+    fn closest_approach(&self, secondary: &ScreeningObject, mut start: f64, mut end: f64) -> f64 {
+        let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+        while end - start > CONJUNCTION_TIME_TOLERANCE_SECS {
+            let lower = end - ratio * (end - start);
+            let upper = start + ratio * (end - start);
+            if self.distance(secondary, lower) > self.distance(secondary, upper) {
+                start = lower;
+            } else {
+                end = upper;
+            }
+        }
+        (start + end) / 2.0
+    }
+
+    fn conjunction(&self, secondary: &ScreeningObject, unix_time: f64) -> Conjunction {
+        let primary_state = self.primary.elements.propagate(self.model, unix_time);
+        let secondary_state = secondary.elements.propagate(self.model, unix_time);
+        Conjunction {
+            secondary: ConjunctionObject::from(secondary),
+            time_of_closest_approach: from_unix_seconds(unix_time),
+            miss_distance: primary_state.position.sub(&secondary_state.position).norm(),
+            relative_speed: primary_state.velocity.sub(&secondary_state.velocity).norm(),
+        }
+    }
+
+    // Every local minimum of the distance below the threshold within [from, to]
+    fn screen(&self, secondary: &ScreeningObject, from: f64, to: f64) -> Vec<Conjunction> {
+        if !self.shells_overlap(secondary) {
+            return Vec::new();
+        }
+        let steps = ((to - from) / CONJUNCTION_SCAN_STEP_SECS).ceil() as usize;
+        let times = (0..=steps)
+            .map(|index| (from + index as f64 * CONJUNCTION_SCAN_STEP_SECS).min(to))
+            .collect::<Vec<_>>();
+        let distances = times
+            .iter()
+            .map(|time| self.distance(secondary, *time))
+            .collect::<Vec<_>>();
+
+        let mut conjunctions = Vec::new();
+        for index in 0..times.len() {
+            let before = index.saturating_sub(1);
+            let after = (index + 1).min(times.len() - 1);
+            // a sampled minimum brackets the true one between its neighbouring samples
+            if distances[index] > distances[before] || distances[index] > distances[after] {
+                continue;
+            }
+            // a flat stretch would report the same approach twice
+            if index > 0 && distances[index] == distances[before] {
+                continue;
+            }
+            let time = self.closest_approach(secondary, times[before], times[after]);
+            let conjunction = self.conjunction(secondary, time);
+            if conjunction.miss_distance <= self.threshold {
+                conjunctions.push(conjunction);
+            }
+        }
+        conjunctions
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ConjunctionQuery {
+    // another satellite as <user_authority>/<registry_authority>/<norad_id>; the whole
+    // registry is screened when absent
+    pub with: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // km
+    pub threshold: Option<f64>,
+    pub model: Option<PropagationModel>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ConjunctionsApiResponse {
+    pub primary: ConjunctionObject,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub threshold: f64,
+    pub model: PropagationModel,
+    // satellites propagated against the primary
+    pub screened: usize,
+    pub conjunctions: Vec<Conjunction>,
+}
+
+fn parse_with(with: &str) -> Result<SatelliteSeeds, ApiError> {
+    let parts = with.split('/').collect::<Vec<_>>();
+    let [user_authority, registry_authority, norad_id] = parts.as_slice() else {
+        return Err(ApiError::InvalidQuery {
+            reason: format!(
+                "with '{}' must be <user_authority>/<registry_authority>/<norad_id>",
+                with
+            ),
+        });
+    };
+    SatelliteSeeds::parse(user_authority, registry_authority, norad_id).map_err(|e| {
+        ApiError::InvalidQuery {
+            reason: format!("with: {}", e.message()),
+        }
+    })
+}
+
+// Every decodable satellite in the registry with propagatable elements
+async fn registry_objects(app_state: &AppState) -> Result<Vec<ScreeningObject>, ApiError> {
+    let filters = SatelliteListQuery::default().rpc_filters()?;
+    let accounts = app_state
+        .account_source
+        .get_program_accounts(&app_state.program_id, filters)
+        .await
+        .map_err(|e| {
+            eprintln!(
+                "Error fetching program accounts for {}: {:?}",
+                app_state.program_id, e
+            );
+            ApiError::rpc(&e, Some(app_state.program_id))
+        })?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let object = decode_satellite_account(&address, &account, &app_state.program_id)
+                .map_err(ApiError::from)
+                .and_then(|satellite| ScreeningObject::new(address, satellite.into()));
+            match object {
+                Ok(object) => Some(object),
+                Err(e) => {
+                    eprintln!("Skipping account {} in screening: {}", address, e.message());
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+#[debug_handler]
+pub async fn get_satellite_conjunctions(
+    SatellitePath(seeds): SatellitePath,
+    ValidQuery(query): ValidQuery<ConjunctionQuery>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<ConjunctionsApiResponse>, ApiError> {
+    let threshold = query.threshold.unwrap_or(DEFAULT_CONJUNCTION_THRESHOLD_KM);
+    if !threshold.is_finite() || threshold <= 0.0 {
+        return Err(ApiError::InvalidQuery {
+            reason: format!("threshold {} must be a positive distance in km", threshold),
+        });
+    }
+    let secondary_seeds = query.with.as_deref().map(parse_with).transpose()?;
+    let (from, to) = parse_window(
+        query.from.as_deref(),
+        query.to.as_deref(),
+        Duration::days(1),
+    )?;
+    let model = query.model.unwrap_or(PropagationModel::TwoBody);
+
+    let primary = fetch_satellite(&app_state, &seeds).await?;
+    let primary = ScreeningObject::new(primary.address, primary.satellite.clone())?;
+    let secondaries = match secondary_seeds {
+        Some(secondary_seeds) => {
+            let secondary = fetch_satellite(&app_state, &secondary_seeds).await?;
+            vec![ScreeningObject::new(
+                secondary.address,
+                secondary.satellite.clone(),
+            )?]
+        }
+        None => registry_objects(&app_state).await?,
+    };
+    let secondaries = secondaries
+        .into_iter()
+        .filter(|secondary| secondary.address != primary.address)
+        .collect::<Vec<_>>();
+
+    println!(
+        "Screening NORAD ID {} against {} satellites from {} to {} below {} km",
+        primary.norad_id,
+        secondaries.len(),
+        from,
+        to,
+        threshold
+    );
+    // propagation over the whole registry is CPU bound, keep it off the async workers
+    let (start, end) = (unix_seconds(&from), unix_seconds(&to));
+    let screened = secondaries.len();
+    let (primary, conjunctions) = tokio::task::spawn_blocking(move || {
+        let screener = ConjunctionScreener {
+            primary: &primary,
+            model,
+            threshold,
+        };
+        let mut conjunctions = secondaries
+            .iter()
+            .flat_map(|secondary| screener.screen(secondary, start, end))
+            .collect::<Vec<_>>();
+        conjunctions.sort_by_key(|conjunction| conjunction.time_of_closest_approach);
+        (primary, conjunctions)
+    })
+    .await
+    .map_err(|e| ApiError::Internal {
+        reason: format!("conjunction screening failed: {}", e),
+    })?;
+    println!("Found {} conjunctions", conjunctions.len());
+
+    Ok(Json(ConjunctionsApiResponse {
+        primary: ConjunctionObject::from(&primary),
+        from,
+        to,
+        threshold,
+        model,
+        screened,
+        conjunctions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EARTH_EQUATORIAL_RADIUS, EARTH_MU};
+
+    fn circular(norad_id: u64, radius: f64, mean_anomaly: f64) -> ScreeningObject {
+        ScreeningObject {
+            address: Pubkey::new_unique(),
+            norad_id,
+            name: format!("SAT-{}", norad_id),
+            elements: KeplerianElements {
+                semi_major_axis: radius,
+                eccentricity: 0.0,
+                inclination: 51.6,
+                raan: 120.0,
+                arg_of_periapsis: 0.0,
+                mean_anomaly,
+                epoch: 1_700_000_000,
+            },
+        }
+    }
+
+    // Co-planar circular orbits 3 km apart, the outer one starting 0.5 degrees ahead: the
+    // inner one catches up once the phase offset has closed, passing exactly r2 - r1 below it
+    #[test]
+    fn coplanar_phase_offset_gives_analytic_miss_distance() {
+        let inner_radius = EARTH_EQUATORIAL_RADIUS + 400.0;
+        let outer_radius = inner_radius + 3.0;
+        let phase_offset = 0.5;
+        let primary = circular(1, inner_radius, 0.0);
+        let secondary = circular(2, outer_radius, phase_offset);
+        let epoch = primary.elements.epoch as f64;
+
+        let closing_rate = primary.elements.mean_motion() - secondary.elements.mean_motion();
+        let expected_time = epoch + phase_offset.to_radians() / closing_rate;
+        let screener = ConjunctionScreener {
+            primary: &primary,
+            model: PropagationModel::TwoBody,
+            threshold: DEFAULT_CONJUNCTION_THRESHOLD_KM,
+        };
+
+        let conjunctions = screener.screen(&secondary, epoch, epoch + 6.0 * 3_600.0);
+        assert_eq!(conjunctions.len(), 1);
+        let conjunction = &conjunctions[0];
+        assert_eq!(conjunction.secondary.norad_id, 2);
+        assert!((conjunction.miss_distance - 3.0).abs() < 1e-6);
+        let time = unix_seconds(&conjunction.time_of_closest_approach);
+        assert!(
+            (time - expected_time).abs() < 5.0,
+            "{} vs {}",
+            time,
+            expected_time
+        );
+        // the velocities are parallel at alignment, so only the circular speeds differ
+        let expected_speed = (EARTH_MU / inner_radius).sqrt() - (EARTH_MU / outer_radius).sqrt();
+        assert!((conjunction.relative_speed - expected_speed).abs() < 1e-6);
+
+        // the same approach is no conjunction under a tighter threshold
+        let tight = ConjunctionScreener {
+            threshold: 2.0,
+            ..screener
+        };
+        assert!(tight
+            .screen(&secondary, epoch, epoch + 6.0 * 3_600.0)
+            .is_empty());
+
+        // nor does a shell 50 km further out ever come close enough
+        let distant = circular(3, inner_radius + 50.0, phase_offset);
+        assert!(!screener.shells_overlap(&distant));
+        assert!(screener
+            .screen(&distant, epoch, epoch + 6.0 * 3_600.0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,496 @@
+use std::io;
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::RpcError,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{RpcRateLimited, SatelliteAccountError};
+
+// Every handler error ends up here so clients always get the same JSON shape:
+// { "code": "...", "message": "...", "details": { ... } }
+#[derive(Debug)]
+pub enum ApiError {
+    InvalidPathParameter {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+    InvalidQuery {
+        reason: String,
+    },
+    InvalidBody {
+        reason: String,
+    },
+    Validation {
+        field: &'static str,
+        reason: String,
+    },
+    InvalidOrbit {
+        reason: String,
+    },
+    NotFound {
+        resource: &'static str,
+        id: String,
+    },
+    AccountNotFound {
+        address: Pubkey,
+    },
+    Conflict {
+        resource: &'static str,
+        field: &'static str,
+        value: String,
+    },
+    SatelliteAccount(SatelliteAccountError),
+    Rpc {
+        class: RpcErrorClass,
+        message: String,
+        address: Option<Pubkey>,
+        // seconds, as the rpc node asked for or our own default for the class
+        retry_after: Option<u64>,
+    },
+    RouteNotFound {
+        path: String,
+    },
+    MethodNotAllowed {
+        method: String,
+        path: String,
+    },
+    UpgradeRequired {
+        protocol: &'static str,
+        reason: String,
+    },
+    Internal {
+        reason: String,
+    },
+}
+
+// How long clients should back off when the upstream rpc node is struggling and did not
+// say for how long itself
+pub const RPC_RETRY_AFTER_SECS: u64 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RpcErrorClass {
+    Timeout,
+    Unavailable,
+    RateLimited,
+    MalformedResponse,
+    Other(&'static str),
+}
+
+impl RpcErrorClass {
+    pub fn classify(error: &ClientError) -> Self {
+        match error.kind() {
+            ClientErrorKind::Reqwest(e) => {
+                if e.is_timeout() {
+                    RpcErrorClass::Timeout
+                } else if e.is_connect() {
+                    RpcErrorClass::Unavailable
+                } else if e.is_decode() {
+                    RpcErrorClass::MalformedResponse
+                } else {
+                    match e.status().map(|status| status.as_u16()) {
+                        Some(429) => RpcErrorClass::RateLimited,
+                        Some(502..=504) => RpcErrorClass::Unavailable,
+                        _ => RpcErrorClass::Other("http"),
+                    }
+                }
+            }
+            ClientErrorKind::Io(e) => match e.kind() {
+                io::ErrorKind::TimedOut => RpcErrorClass::Timeout,
+                io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected => RpcErrorClass::Unavailable,
+                _ => RpcErrorClass::Other("io"),
+            },
+            ClientErrorKind::SerdeJson(_) => RpcErrorClass::MalformedResponse,
+            ClientErrorKind::RpcError(RpcError::ParseError(_)) => RpcErrorClass::MalformedResponse,
+            // some providers report throttling as a json-rpc error instead of an http status
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code: 429, .. }) => {
+                RpcErrorClass::RateLimited
+            }
+            ClientErrorKind::RpcError(_) => RpcErrorClass::Other("rpc"),
+            ClientErrorKind::Middleware(e) if e.is::<RpcRateLimited>() => {
+                RpcErrorClass::RateLimited
+            }
+            ClientErrorKind::Middleware(_) => RpcErrorClass::Other("middleware"),
+            ClientErrorKind::SigningError(_) => RpcErrorClass::Other("signing"),
+            ClientErrorKind::TransactionError(_) => RpcErrorClass::Other("transaction"),
+            ClientErrorKind::Custom(_) => RpcErrorClass::Other("custom"),
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            RpcErrorClass::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            RpcErrorClass::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            RpcErrorClass::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            RpcErrorClass::MalformedResponse => StatusCode::BAD_GATEWAY,
+            RpcErrorClass::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            RpcErrorClass::Timeout => "rpc_timeout",
+            RpcErrorClass::Unavailable => "rpc_unavailable",
+            RpcErrorClass::RateLimited => "rpc_rate_limited",
+            RpcErrorClass::MalformedResponse => "rpc_malformed_response",
+            RpcErrorClass::Other(_) => "rpc_error",
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RpcErrorClass::Timeout => "timeout",
+            RpcErrorClass::Unavailable => "unavailable",
+            RpcErrorClass::RateLimited => "rate_limited",
+            RpcErrorClass::MalformedResponse => "malformed_response",
+            RpcErrorClass::Other(kind) => kind,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            RpcErrorClass::Timeout | RpcErrorClass::Unavailable | RpcErrorClass::RateLimited => {
+                Some(RPC_RETRY_AFTER_SECS)
+            }
+            RpcErrorClass::MalformedResponse | RpcErrorClass::Other(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ApiErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn rpc(error: &ClientError, address: Option<Pubkey>) -> Self {
+        let class = RpcErrorClass::classify(error);
+        let upstream_retry_after = match error.kind() {
+            ClientErrorKind::Middleware(e) => e
+                .downcast_ref::<RpcRateLimited>()
+                .and_then(|rate_limited| rate_limited.retry_after),
+            _ => None,
+        };
+        ApiError::Rpc {
+            class,
+            message: error.to_string(),
+            address,
+            retry_after: upstream_retry_after.or(class.retry_after()),
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidPathParameter { .. }
+            | ApiError::InvalidQuery { .. }
+            | ApiError::InvalidBody { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Validation { .. } | ApiError::InvalidOrbit { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ApiError::NotFound { .. }
+            | ApiError::AccountNotFound { .. }
+            | ApiError::RouteNotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::SatelliteAccount(e) => e.status_code(),
+            ApiError::Rpc { class, .. } => class.status_code(),
+            ApiError::MethodNotAllowed { .. } => StatusCode::METHOD_NOT_ALLOWED,
+            ApiError::UpgradeRequired { .. } => StatusCode::UPGRADE_REQUIRED,
+            ApiError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Stable, machine-readable identifier clients can match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidPathParameter { .. } => "invalid_path_parameter",
+            ApiError::InvalidQuery { .. } => "invalid_query",
+            ApiError::InvalidBody { .. } => "invalid_body",
+            ApiError::Validation { .. } => "validation_failed",
+            ApiError::InvalidOrbit { .. } => "invalid_orbital_elements",
+            ApiError::NotFound { .. } => "not_found",
+            ApiError::AccountNotFound { .. } => "account_not_found",
+            ApiError::Conflict { .. } => "conflict",
+            ApiError::SatelliteAccount(e) => e.code(),
+            ApiError::Rpc { class, .. } => class.code(),
+            ApiError::RouteNotFound { .. } => "route_not_found",
+            ApiError::MethodNotAllowed { .. } => "method_not_allowed",
+            ApiError::UpgradeRequired { .. } => "upgrade_required",
+            ApiError::Internal { .. } => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::InvalidPathParameter {
+                name,
+                value,
+                reason,
+            } => format!("Invalid path parameter {} '{}': {}", name, value, reason),
+            ApiError::InvalidQuery { reason } => format!("Invalid query string: {}", reason),
+            ApiError::InvalidBody { reason } => format!("Invalid request body: {}", reason),
+            ApiError::Validation { field, reason } => format!("Invalid {}: {}", field, reason),
+            ApiError::InvalidOrbit { reason } => {
+                format!("Stored orbital elements cannot be propagated: {}", reason)
+            }
+            ApiError::NotFound { resource, id } => format!("No {} found for '{}'", resource, id),
+            ApiError::AccountNotFound { address } => format!("Account {} does not exist", address),
+            ApiError::Conflict {
+                resource,
+                field,
+                value,
+            } => format!("A {} with {} '{}' already exists", resource, field, value),
+            ApiError::SatelliteAccount(e) => e.message(),
+            ApiError::Rpc { message, .. } => format!("RPC request failed: {}", message),
+            ApiError::RouteNotFound { path } => format!("No route matches {}", path),
+            ApiError::MethodNotAllowed { method, path } => {
+                format!("Method {} is not allowed on {}", method, path)
+            }
+            ApiError::UpgradeRequired { protocol, reason } => {
+                format!("This endpoint requires a {} upgrade: {}", protocol, reason)
+            }
+            ApiError::Internal { reason } => format!("Internal error: {}", reason),
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            ApiError::InvalidPathParameter { name, value, .. } => {
+                Some(json!({ "parameter": name, "value": value }))
+            }
+            ApiError::InvalidQuery { .. } | ApiError::InvalidBody { .. } => None,
+            ApiError::InvalidOrbit { reason } => Some(json!({ "reason": reason })),
+            ApiError::Validation { field, reason } => {
+                Some(json!({ "field": field, "reason": reason }))
+            }
+            ApiError::NotFound { resource, id } => Some(json!({ "resource": resource, "id": id })),
+            ApiError::AccountNotFound { address } => {
+                Some(json!({ "address": address.to_string() }))
+            }
+            ApiError::Conflict {
+                resource,
+                field,
+                value,
+            } => Some(json!({ "resource": resource, "field": field, "value": value })),
+            ApiError::SatelliteAccount(e) => Some(e.details()),
+            ApiError::Rpc {
+                class,
+                address,
+                retry_after,
+                ..
+            } => Some(json!({
+                "rpc_error_kind": class.kind(),
+                "retry_after_secs": retry_after,
+                "address": address.map(|address| address.to_string()),
+            })),
+            ApiError::RouteNotFound { path } => Some(json!({ "path": path })),
+            ApiError::MethodNotAllowed { method, path } => {
+                Some(json!({ "method": method, "path": path }))
+            }
+            ApiError::UpgradeRequired { protocol, .. } => Some(json!({ "protocol": protocol })),
+            ApiError::Internal { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<SatelliteAccountError> for ApiError {
+    fn from(error: SatelliteAccountError) -> Self {
+        ApiError::SatelliteAccount(error)
+    }
+}
+
+impl From<&ApiError> for ApiErrorBody {
+    fn from(error: &ApiError) -> Self {
+        ApiErrorBody {
+            code: error.code(),
+            message: error.message(),
+            details: error.details(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ApiErrorBody::from(&self);
+        let mut response = (self.status_code(), Json(body)).into_response();
+        if let ApiError::Rpc {
+            retry_after: Some(retry_after),
+            ..
+        } = &self
+        {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, (*retry_after).into());
+        }
+        response
+    }
+}
+
+// Query string extractor that fails with the usual JSON error body instead of axum's plain text
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValidQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::InvalidQuery {
+                reason: e.body_text(),
+            })?;
+        Ok(ValidQuery(query))
+    }
+}
+
+pub async fn route_not_found(uri: Uri) -> ApiError {
+    ApiError::RouteNotFound {
+        path: uri.path().to_string(),
+    }
+}
+
+pub async fn method_not_allowed(method: Method, uri: Uri) -> ApiError {
+    ApiError::MethodNotAllowed {
+        method: method.to_string(),
+        path: uri.path().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(router: Router, uri: &str) -> (StatusCode, Value) {
+        let response = router
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct LimitQuery {
+        limit: u32,
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/items",
+                get(|ValidQuery(query): ValidQuery<LimitQuery>| async move { Json(query.limit) }),
+            )
+            .fallback(route_not_found)
+            .method_not_allowed_fallback(method_not_allowed)
+    }
+
+    #[tokio::test]
+    async fn errors_render_code_message_and_details() {
+        let (status, body) = send(router(), "/items?limit=many").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_query");
+        assert!(body["message"].as_str().unwrap().contains("limit"));
+        assert!(body.get("details").is_none());
+
+        let (status, body) = send(router(), "/nowhere").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "route_not_found");
+        assert_eq!(body["details"]["path"], "/nowhere");
+
+        let response = router()
+            .oneshot(Request::post("/items").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn valid_query_passes_well_formed_queries_through() {
+        let (status, body) = send(router(), "/items?limit=3").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, 3);
+    }
+
+    fn rate_limited(retry_after: Option<u64>) -> ApiError {
+        let error = ClientError::from(ClientErrorKind::Middleware(
+            RpcRateLimited { retry_after }.into(),
+        ));
+        ApiError::rpc(&error, None)
+    }
+
+    #[test]
+    fn rpc_errors_are_classified_by_cause() {
+        let classify = |kind: ClientErrorKind| RpcErrorClass::classify(&ClientError::from(kind));
+        assert_eq!(
+            classify(ClientErrorKind::Io(io::ErrorKind::TimedOut.into())),
+            RpcErrorClass::Timeout
+        );
+        assert_eq!(
+            classify(ClientErrorKind::Io(io::ErrorKind::ConnectionRefused.into())),
+            RpcErrorClass::Unavailable
+        );
+        assert_eq!(
+            classify(ClientErrorKind::RpcError(RpcError::ParseError(
+                "not json".to_string()
+            ))),
+            RpcErrorClass::MalformedResponse
+        );
+        assert_eq!(
+            classify(ClientErrorKind::Custom("boom".to_string())),
+            RpcErrorClass::Other("custom")
+        );
+    }
+
+    #[test]
+    fn rate_limit_passes_upstream_retry_after_through() {
+        let response = rate_limited(Some(17)).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "17");
+    }
+
+    #[test]
+    fn rate_limit_without_retry_after_uses_default() {
+        let response = rate_limited(None).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response.headers()[header::RETRY_AFTER],
+            RPC_RETRY_AFTER_SECS.to_string().as_str()
+        );
+    }
+}
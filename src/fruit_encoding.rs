@@ -0,0 +1,266 @@
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{ApiError, FruitRepresentation};
+
+pub const BORSH_CONTENT_TYPE: &str = "application/x-borsh";
+// also accepted for Borsh, and echoed back to clients that ask for it
+pub const OCTET_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+
+// What fruit payloads are written in, on the way in and out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FruitEncoding {
+    Json,
+    // the same layout the BorshDeserialize derives on the fruit types read, under whichever
+    // media type the client named
+    Borsh(&'static str),
+}
+
+impl FruitEncoding {
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type.trim().to_ascii_lowercase().as_str() {
+            "application/json" => Some(FruitEncoding::Json),
+            BORSH_CONTENT_TYPE => Some(FruitEncoding::Borsh(BORSH_CONTENT_TYPE)),
+            OCTET_STREAM_CONTENT_TYPE => Some(FruitEncoding::Borsh(OCTET_STREAM_CONTENT_TYPE)),
+            _ => None,
+        }
+    }
+
+    // The supported encoding the client ranks highest; JSON when it names neither
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let mut best: Option<(FruitEncoding, f32)> = None;
+        let ranges = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+        for range in ranges {
+            let mut parts = range.split(';');
+            let Some(encoding) = parts.next().and_then(Self::from_media_type) else {
+                continue;
+            };
+            let quality = parts
+                .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                .find_map(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            // on a tie the first one listed wins
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+            .unwrap_or(FruitEncoding::Json)
+    }
+
+    // Request bodies say what they are in Content-Type, which has to be JSON or Borsh
+    pub fn from_content_type(headers: &HeaderMap) -> Result<Self, ApiError> {
+        let Some(content_type) = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Err(ApiError::InvalidBody {
+                reason: format!(
+                    "Content-Type must be application/json or {}",
+                    BORSH_CONTENT_TYPE
+                ),
+            });
+        };
+        content_type
+            .split(';')
+            .next()
+            .and_then(Self::from_media_type)
+            .ok_or_else(|| ApiError::InvalidBody {
+                reason: format!(
+                    "Content-Type '{}' must be application/json or {}",
+                    content_type, BORSH_CONTENT_TYPE
+                ),
+            })
+    }
+
+    pub fn decode<T>(headers: &HeaderMap, body: &Bytes) -> Result<T, ApiError>
+    where
+        T: serde::de::DeserializeOwned + BorshDeserialize,
+    {
+        match Self::from_content_type(headers)? {
+            FruitEncoding::Json => {
+                serde_json::from_slice(body).map_err(|e| ApiError::InvalidBody {
+                    reason: format!("Failed to deserialize the JSON body: {}", e),
+                })
+            }
+            FruitEncoding::Borsh(_) => T::try_from_slice(body).map_err(|e| ApiError::InvalidBody {
+                reason: format!("Failed to deserialize the Borsh body: {}", e),
+            }),
+        }
+    }
+
+    pub fn render<T>(&self, status: StatusCode, value: &T) -> Result<Response, ApiError>
+    where
+        T: serde::Serialize + BorshSerialize,
+    {
+        // the representation depends on Accept, so shared caches must key on it
+        let vary = [(header::VARY, HeaderValue::from_static("accept"))];
+        match self {
+            FruitEncoding::Json => Ok((status, vary, Json(value)).into_response()),
+            FruitEncoding::Borsh(media_type) => {
+                let bytes = borsh::to_vec(value).map_err(|e| ApiError::Internal {
+                    reason: format!("encoding Borsh response: {}", e),
+                })?;
+                Ok((status, vary, [(header::CONTENT_TYPE, *media_type)], bytes).into_response())
+            }
+        }
+    }
+}
+
+// Untagged in JSON, so in Borsh too: each shape is written as its own struct
+impl BorshSerialize for FruitRepresentation {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            FruitRepresentation::Current(fruit) => fruit.serialize(writer),
+            FruitRepresentation::Legacy(fruit) => fruit.serialize(writer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        default_fruits, FruitPatch, FruitRepresentation, FruitRequest, LegacyFruit, NutrientInput,
+        NutrientUnit,
+    };
+
+    // Fruit as the baseline server defined it, the layout legacy Borsh clients were built on
+    #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    struct BaselineFruit {
+        name: String,
+        nutrients: Vec<String>,
+        id: Option<String>,
+    }
+
+    fn headers(name: header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn borsh_headers() -> HeaderMap {
+        headers(header::CONTENT_TYPE, BORSH_CONTENT_TYPE)
+    }
+
+    #[test]
+    fn accept_picks_highest_quality() {
+        let accept = |value| FruitEncoding::from_accept(&headers(header::ACCEPT, value));
+        assert_eq!(
+            accept("application/json;q=0.5, application/x-borsh"),
+            FruitEncoding::Borsh(BORSH_CONTENT_TYPE)
+        );
+        assert_eq!(
+            accept("application/x-borsh;q=0.2, application/json;q=0.9"),
+            FruitEncoding::Json
+        );
+        assert_eq!(
+            accept("application/octet-stream, application/json"),
+            FruitEncoding::Borsh(OCTET_STREAM_CONTENT_TYPE)
+        );
+        assert_eq!(
+            accept("application/x-borsh;q=0, text/html"),
+            FruitEncoding::Json
+        );
+        assert_eq!(
+            FruitEncoding::from_accept(&HeaderMap::new()),
+            FruitEncoding::Json
+        );
+    }
+
+    #[test]
+    fn render_echoes_negotiated_media_type() {
+        let encoding =
+            FruitEncoding::from_accept(&headers(header::ACCEPT, "application/octet-stream"));
+        let response = encoding
+            .render(StatusCode::OK, &"kiwi".to_string())
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            OCTET_STREAM_CONTENT_TYPE
+        );
+    }
+
+    #[test]
+    fn content_type_must_be_json_or_borsh() {
+        assert!(FruitEncoding::from_content_type(&HeaderMap::new()).is_err());
+        assert!(
+            FruitEncoding::from_content_type(&headers(header::CONTENT_TYPE, "text/plain")).is_err()
+        );
+        assert_eq!(
+            FruitEncoding::from_content_type(&headers(
+                header::CONTENT_TYPE,
+                "application/json; charset=utf-8"
+            ))
+            .unwrap(),
+            FruitEncoding::Json
+        );
+        let body = Bytes::from_static(br#"{"name":"kiwi"}"#);
+        let error = FruitEncoding::decode::<FruitRequest>(
+            &headers(header::CONTENT_TYPE, "application/xml"),
+            &body,
+        )
+        .unwrap_err();
+        assert!(matches!(error, ApiError::InvalidBody { .. }));
+    }
+
+    #[test]
+    fn borsh_round_trips_request_bodies() {
+        let nutrients = vec![
+            NutrientInput::Name("fiber".to_string()),
+            NutrientInput::Measured {
+                id: "vitamin_c".to_string(),
+                amount_per_100g: Some(92.7),
+                unit: Some(NutrientUnit::Milligram),
+            },
+        ];
+        let request = FruitRequest {
+            name: "kiwi".to_string(),
+            nutrients,
+        };
+        let body = Bytes::from(borsh::to_vec(&request).unwrap());
+        let decoded = FruitEncoding::decode::<FruitRequest>(&borsh_headers(), &body).unwrap();
+        assert_eq!(decoded, request);
+
+        let patch = FruitPatch {
+            name: None,
+            nutrients: Some(request.nutrients),
+        };
+        let body = Bytes::from(borsh::to_vec(&patch).unwrap());
+        let decoded = FruitEncoding::decode::<FruitPatch>(&borsh_headers(), &body).unwrap();
+        assert_eq!(decoded, patch);
+
+        let truncated = body.slice(..body.len() - 1);
+        assert!(FruitEncoding::decode::<FruitPatch>(&borsh_headers(), &truncated).is_err());
+    }
+
+    #[test]
+    fn legacy_borsh_matches_the_baseline_fruit_layout() {
+        let fruit = default_fruits().remove(0);
+        let id = fruit.id;
+        let bytes = borsh::to_vec(&FruitRepresentation::new(fruit, true)).unwrap();
+        let baseline = borsh::from_slice::<BaselineFruit>(&bytes).unwrap();
+        assert_eq!(
+            baseline,
+            BaselineFruit {
+                name: "banana".to_string(),
+                nutrients: vec!["potassium".to_string(), "vitamin B6".to_string()],
+                id: Some(id.to_string()),
+            }
+        );
+
+        // and what a baseline client writes reads back unchanged
+        let legacy = borsh::from_slice::<LegacyFruit>(&borsh::to_vec(&baseline).unwrap()).unwrap();
+        assert_eq!(legacy.id, baseline.id);
+        assert_eq!(legacy.nutrients, baseline.nutrients);
+    }
+}
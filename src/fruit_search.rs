@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE, Engine};
+use borsh::BorshSerialize;
+
+use crate::{ApiError, Fruit, FruitRepresentation, NutrientInfo};
+
+pub const DEFAULT_FRUIT_PAGE_SIZE: usize = 20;
+pub const MAX_FRUIT_PAGE_SIZE: usize = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FruitMatch {
+    // names starting with the search text
+    #[default]
+    Prefix,
+    // names containing the search text or within a couple of typos of it
+    Fuzzy,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+pub enum FruitSort {
+    #[default]
+    #[serde(rename = "name")]
+    NameAscending,
+    #[serde(rename = "-name")]
+    NameDescending,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct FruitListQuery {
+    pub search: Option<String>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<FruitMatch>,
+    // comma-separated ids, names or aliases; a fruit has to list every one of them
+    pub nutrient: Option<String>,
+    pub sort: Option<FruitSort>,
+    pub limit: Option<usize>,
+    // next_cursor from the previous page
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub legacy_nutrients: bool,
+}
+
+// total is a u64 in Borsh
+#[derive(Debug, serde::Serialize, BorshSerialize)]
+pub struct FruitListApiResponse {
+    pub fruits: Vec<FruitRepresentation>,
+    // fruits matching the search and filters across all pages
+    pub total: usize,
+    // pass as ?cursor= for the next page; absent on the last one
+    pub next_cursor: Option<String>,
+}
+
+// Edit distance counted in characters
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Expects lowercase input. One typo is tolerated per four characters typed, against the whole
+// name or any stretch of it as long as the search, so "bery" still finds the berries
+fn fuzzy_matches(search: &str, name: &str) -> bool {
+    if name.contains(search) {
+        return true;
+    }
+    let search = search.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let allowed = (search.len() / 4).max(1);
+    levenshtein(&search, &name) <= allowed
+        || name
+            .windows(search.len())
+            .any(|window| levenshtein(&search, window) <= allowed)
+}
+
+// The order pages are cut in; names are unique, so this is total
+fn sort_key(name: &str) -> (String, &str) {
+    (name.to_lowercase(), name)
+}
+
+fn encode_cursor(fruit: &Fruit) -> String {
+    BASE64_URL_SAFE.encode(fruit.name.as_bytes())
+}
+
+fn decode_cursor(cursor: &str) -> Result<String, ApiError> {
+    BASE64_URL_SAFE
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| ApiError::InvalidQuery {
+            reason: format!("cursor '{}' was not returned by this endpoint", cursor),
+        })
+}
+
+// A validated FruitListQuery
+#[derive(Debug)]
+pub struct FruitSearch {
+    search: Option<String>,
+    match_mode: FruitMatch,
+    // canonical ids
+    nutrients: Vec<&'static str>,
+    sort: FruitSort,
+    limit: usize,
+    after: Option<String>,
+    legacy_nutrients: bool,
+}
+
+impl FruitSearch {
+    pub fn parse(query: FruitListQuery) -> Result<Self, ApiError> {
+        let limit = query.limit.unwrap_or(DEFAULT_FRUIT_PAGE_SIZE);
+        if limit == 0 || limit > MAX_FRUIT_PAGE_SIZE {
+            return Err(ApiError::InvalidQuery {
+                reason: format!(
+                    "limit {} must be between 1 and {}",
+                    limit, MAX_FRUIT_PAGE_SIZE
+                ),
+            });
+        }
+        let mut nutrients = Vec::new();
+        for nutrient in query
+            .nutrient
+            .iter()
+            .flat_map(|nutrient| nutrient.split(','))
+        {
+            if nutrient.trim().is_empty() {
+                continue;
+            }
+            let info = NutrientInfo::lookup(nutrient).ok_or_else(|| ApiError::InvalidQuery {
+                reason: format!("unknown nutrient '{}'", nutrient.trim()),
+            })?;
+            nutrients.push(info.id);
+        }
+        Ok(FruitSearch {
+            search: query
+                .search
+                .map(|search| search.trim().to_lowercase())
+                .filter(|search| !search.is_empty()),
+            match_mode: query.match_mode.unwrap_or_default(),
+            nutrients,
+            sort: query.sort.unwrap_or_default(),
+            limit,
+            after: query.cursor.as_deref().map(decode_cursor).transpose()?,
+            legacy_nutrients: query.legacy_nutrients,
+        })
+    }
+
+    fn matches(&self, fruit: &Fruit) -> bool {
+        let name = fruit.name.to_lowercase();
+        let name_matches = match (&self.search, self.match_mode) {
+            (None, _) => true,
+            (Some(search), FruitMatch::Prefix) => name.starts_with(search.as_str()),
+            (Some(search), FruitMatch::Fuzzy) => fuzzy_matches(search, &name),
+        };
+        name_matches
+            && self.nutrients.iter().all(|wanted| {
+                fruit
+                    .nutrients
+                    .iter()
+                    .any(|nutrient| nutrient.id == *wanted)
+            })
+    }
+
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        let ordering = sort_key(a).cmp(&sort_key(b));
+        match self.sort {
+            FruitSort::NameAscending => ordering,
+            FruitSort::NameDescending => ordering.reverse(),
+        }
+    }
+
+    // Filter, sort and cut out the page after the cursor
+    pub fn page(&self, fruits: Vec<Fruit>) -> FruitListApiResponse {
+        let mut fruits = fruits
+            .into_iter()
+            .filter(|fruit| self.matches(fruit))
+            .collect::<Vec<_>>();
+        fruits.sort_by(|a, b| self.compare(&a.name, &b.name));
+        let total = fruits.len();
+
+        let start = match &self.after {
+            Some(after) => fruits
+                .partition_point(|fruit| self.compare(&fruit.name, after) != Ordering::Greater),
+            None => 0,
+        };
+        let mut page = fruits
+            .into_iter()
+            .skip(start)
+            .take(self.limit + 1)
+            .collect::<Vec<_>>();
+        let next_cursor = if page.len() > self.limit {
+            page.truncate(self.limit);
+            page.last().map(encode_cursor)
+        } else {
+            None
+        };
+
+        FruitListApiResponse {
+            fruits: page
+                .into_iter()
+                .map(|fruit| FruitRepresentation::new(fruit, self.legacy_nutrients))
+                .collect(),
+            total,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fruits(names: &[&str]) -> Vec<Fruit> {
+        names
+            .iter()
+            .map(|name| Fruit::new(name.to_string(), Vec::new()))
+            .collect()
+    }
+
+    fn search(sort: FruitSort, limit: usize, cursor: Option<String>) -> FruitSearch {
+        FruitSearch::parse(FruitListQuery {
+            sort: Some(sort),
+            limit: Some(limit),
+            cursor,
+            ..FruitListQuery::default()
+        })
+        .unwrap()
+    }
+
+    fn names(response: &FruitListApiResponse) -> Vec<&str> {
+        response
+            .fruits
+            .iter()
+            .map(|fruit| match fruit {
+                FruitRepresentation::Current(fruit) => fruit.name.as_str(),
+                FruitRepresentation::Legacy(fruit) => fruit.name.as_str(),
+            })
+            .collect()
+    }
+
+    // Walks every page, checking each one is at most a page long
+    fn walk(sort: FruitSort, all: &[&str]) -> Vec<String> {
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let response = search(sort, 2, cursor).page(fruits(all));
+            assert_eq!(response.total, all.len());
+            assert!(response.fruits.len() <= 2);
+            seen.extend(names(&response).into_iter().map(str::to_string));
+            match response.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return seen,
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_continues_in_both_sort_orders() {
+        let all = ["cherry", "Banana", "apple", "date", "elderberry"];
+        assert_eq!(
+            walk(FruitSort::NameAscending, &all),
+            ["apple", "Banana", "cherry", "date", "elderberry"]
+        );
+        assert_eq!(
+            walk(FruitSort::NameDescending, &all),
+            ["elderberry", "date", "cherry", "Banana", "apple"]
+        );
+    }
+
+    #[test]
+    fn cursor_survives_its_fruit_being_deleted() {
+        let all = ["apple", "banana", "cherry", "date", "elderberry"];
+
+        let first = search(FruitSort::NameAscending, 2, None).page(fruits(&all));
+        assert_eq!(names(&first), ["apple", "banana"]);
+        // banana goes away between the two requests
+        let remaining = ["apple", "cherry", "date", "elderberry"];
+        let second =
+            search(FruitSort::NameAscending, 2, first.next_cursor).page(fruits(&remaining));
+        assert_eq!(names(&second), ["cherry", "date"]);
+
+        let first = search(FruitSort::NameDescending, 2, None).page(fruits(&all));
+        assert_eq!(names(&first), ["elderberry", "date"]);
+        let remaining = ["apple", "banana", "cherry", "elderberry"];
+        let second =
+            search(FruitSort::NameDescending, 2, first.next_cursor).page(fruits(&remaining));
+        assert_eq!(names(&second), ["cherry", "banana"]);
+    }
+
+    #[test]
+    fn fuzzy_matches_tolerates_typos() {
+        assert!(fuzzy_matches("nana", "banana"));
+        // one typo is allowed however short the search
+        assert!(fuzzy_matches("aple", "apple"));
+        assert!(fuzzy_matches("bery", "strawberry"));
+        assert!(!fuzzy_matches("bnaan", "banana"));
+        // a second one from eight characters on
+        assert!(fuzzy_matches("elderbrri", "elderberry"));
+        assert!(!fuzzy_matches("ornge", "banana"));
+    }
+}
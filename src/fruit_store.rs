@@ -0,0 +1,517 @@
+use std::{
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::{default_fruits, ApiError, Fruit, FruitUpdate, Nutrient};
+
+// bumped whenever the fruits table changes shape; 0 means a brand new database
+const FRUIT_SCHEMA_VERSION: i64 = 2;
+
+#[derive(Debug)]
+pub enum FruitStoreError {
+    DuplicateName(String),
+    Storage(String),
+}
+
+impl fmt::Display for FruitStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FruitStoreError::DuplicateName(name) => write!(f, "fruit '{}' already exists", name),
+            FruitStoreError::Storage(reason) => write!(f, "fruit store failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FruitStoreError {}
+
+impl From<rusqlite::Error> for FruitStoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        FruitStoreError::Storage(error.to_string())
+    }
+}
+
+impl From<FruitStoreError> for ApiError {
+    fn from(error: FruitStoreError) -> Self {
+        match error {
+            FruitStoreError::DuplicateName(name) => ApiError::Conflict {
+                resource: "fruit",
+                field: "name",
+                value: name,
+            },
+            FruitStoreError::Storage(reason) => {
+                eprintln!("Fruit store error: {}", reason);
+                ApiError::Internal { reason }
+            }
+        }
+    }
+}
+
+// Where the fruit catalog lives. Names are unique and ids never change once assigned.
+#[async_trait]
+pub trait FruitRepository: Send + Sync {
+    // in the order the fruits were added
+    async fn list(&self) -> Result<Vec<Fruit>, FruitStoreError>;
+
+    async fn get(&self, id: Uuid) -> Result<Option<Fruit>, FruitStoreError>;
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Fruit>, FruitStoreError>;
+
+    async fn insert(&self, fruit: Fruit) -> Result<Fruit, FruitStoreError>;
+
+    // None when no fruit has the id
+    async fn update(&self, id: Uuid, update: FruitUpdate)
+        -> Result<Option<Fruit>, FruitStoreError>;
+
+    // false when no fruit has the id
+    async fn delete(&self, id: Uuid) -> Result<bool, FruitStoreError>;
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryFruitRepository {
+    fruits: RwLock<Vec<Fruit>>,
+}
+
+impl InMemoryFruitRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_default_fruits() -> Self {
+        InMemoryFruitRepository {
+            fruits: RwLock::new(default_fruits()),
+        }
+    }
+}
+
+#[async_trait]
+impl FruitRepository for InMemoryFruitRepository {
+    async fn list(&self) -> Result<Vec<Fruit>, FruitStoreError> {
+        Ok(self.fruits.read().unwrap().clone())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Fruit>, FruitStoreError> {
+        let fruits = self.fruits.read().unwrap();
+        Ok(fruits.iter().find(|fruit| fruit.id == id).cloned())
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Fruit>, FruitStoreError> {
+        let fruits = self.fruits.read().unwrap();
+        Ok(fruits.iter().find(|fruit| fruit.name == name).cloned())
+    }
+
+    async fn insert(&self, fruit: Fruit) -> Result<Fruit, FruitStoreError> {
+        let mut fruits = self.fruits.write().unwrap();
+        if fruits.iter().any(|existing| existing.name == fruit.name) {
+            return Err(FruitStoreError::DuplicateName(fruit.name));
+        }
+        fruits.push(fruit.clone());
+        Ok(fruit)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        update: FruitUpdate,
+    ) -> Result<Option<Fruit>, FruitStoreError> {
+        let mut fruits = self.fruits.write().unwrap();
+        if let Some(name) = &update.name {
+            if fruits
+                .iter()
+                .any(|existing| existing.id != id && existing.name == *name)
+            {
+                return Err(FruitStoreError::DuplicateName(name.clone()));
+            }
+        }
+        Ok(fruits.iter_mut().find(|fruit| fruit.id == id).map(|fruit| {
+            update.apply(fruit);
+            fruit.clone()
+        }))
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, FruitStoreError> {
+        let mut fruits = self.fruits.write().unwrap();
+        let before = fruits.len();
+        fruits.retain(|fruit| fruit.id != id);
+        Ok(fruits.len() != before)
+    }
+}
+
+// rusqlite is blocking, so every query runs on the blocking pool against one shared connection
+#[derive(Clone)]
+pub struct SqliteFruitRepository {
+    connection: Arc<Mutex<Connection>>,
+}
+
+fn is_unique_violation(error: &rusqlite::Error) -> bool {
+    matches!(
+        error,
+        rusqlite::Error::SqliteFailure(e, _) if e.code == ErrorCode::ConstraintViolation
+    )
+}
+
+// nutrients are stored as a JSON array in a single column
+fn fruit_from_row(row: &Row<'_>) -> rusqlite::Result<Fruit> {
+    let id: String = row.get("id")?;
+    let nutrients: String = row.get("nutrients")?;
+    Ok(Fruit {
+        id: Uuid::parse_str(&id).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        name: row.get("name")?,
+        nutrients: serde_json::from_str(&nutrients).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+    })
+}
+
+fn nutrients_json(fruit: &Fruit) -> Result<String, FruitStoreError> {
+    serde_json::to_string(&fruit.nutrients).map_err(|e| FruitStoreError::Storage(e.to_string()))
+}
+
+fn insert_fruit(connection: &Connection, fruit: &Fruit) -> Result<(), FruitStoreError> {
+    connection
+        .execute(
+            "INSERT INTO fruits (id, name, nutrients) VALUES (?1, ?2, ?3)",
+            params![fruit.id.to_string(), fruit.name, nutrients_json(fruit)?],
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                FruitStoreError::DuplicateName(fruit.name.clone())
+            } else {
+                e.into()
+            }
+        })?;
+    Ok(())
+}
+
+fn select_fruit(connection: &Connection, id: Uuid) -> Result<Option<Fruit>, FruitStoreError> {
+    Ok(connection
+        .query_row(
+            "SELECT id, name, nutrients FROM fruits WHERE id = ?1",
+            params![id.to_string()],
+            fruit_from_row,
+        )
+        .optional()?)
+}
+
+// Version 1 stored nutrients as bare names; they become reference nutrients without amounts
+fn migrate_nutrient_names(connection: &Connection) -> Result<(), FruitStoreError> {
+    let rows = {
+        let mut statement = connection.prepare("SELECT id, name, nutrients FROM fruits")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>("id")?,
+                    row.get::<_, String>("name")?,
+                    row.get::<_, String>("nutrients")?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    for (id, name, nutrients) in rows {
+        let names: Vec<String> = serde_json::from_str(&nutrients)
+            .map_err(|e| FruitStoreError::Storage(format!("nutrients of {}: {}", name, e)))?;
+        let mut nutrients: Vec<Nutrient> = Vec::with_capacity(names.len());
+        for nutrient_name in names {
+            match Nutrient::from_name(&nutrient_name) {
+                Some(nutrient) if !nutrients.iter().any(|known| known.id == nutrient.id) => {
+                    nutrients.push(nutrient)
+                }
+                Some(_) => {}
+                None => eprintln!(
+                    "Dropping unknown nutrient '{}' from fruit {}",
+                    nutrient_name, name
+                ),
+            }
+        }
+        let nutrients = serde_json::to_string(&nutrients)
+            .map_err(|e| FruitStoreError::Storage(e.to_string()))?;
+        connection.execute(
+            "UPDATE fruits SET nutrients = ?2 WHERE id = ?1",
+            params![id, nutrients],
+        )?;
+    }
+    Ok(())
+}
+
+impl SqliteFruitRepository {
+    // Opens or creates the database; a new database is seeded with the sample fruits
+    pub fn open(path: &Path) -> Result<Self, FruitStoreError> {
+        Self::init(Connection::open(path)?, &path.display().to_string())
+    }
+
+    // Creates, seeds or migrates the fruits table on a freshly opened connection
+    fn init(mut connection: Connection, location: &str) -> Result<Self, FruitStoreError> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS fruits (
+                id TEXT PRIMARY KEY NOT NULL,
+                name TEXT NOT NULL UNIQUE,
+                nutrients TEXT NOT NULL
+            )",
+        )?;
+
+        let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version == 0 {
+            let transaction = connection.transaction()?;
+            for fruit in default_fruits() {
+                insert_fruit(&transaction, &fruit)?;
+            }
+            transaction.pragma_update(None, "user_version", FRUIT_SCHEMA_VERSION)?;
+            transaction.commit()?;
+            println!("Created fruit database at {}", location);
+        } else if version < FRUIT_SCHEMA_VERSION {
+            let transaction = connection.transaction()?;
+            migrate_nutrient_names(&transaction)?;
+            transaction.pragma_update(None, "user_version", FRUIT_SCHEMA_VERSION)?;
+            transaction.commit()?;
+            println!(
+                "Migrated fruit database at {} to version {}",
+                location, FRUIT_SCHEMA_VERSION
+            );
+        }
+
+        Ok(SqliteFruitRepository {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    async fn run<T, F>(&self, query: F) -> Result<T, FruitStoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, FruitStoreError> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || query(&mut connection.lock().unwrap()))
+            .await
+            .map_err(|e| FruitStoreError::Storage(e.to_string()))?
+    }
+}
+
+#[async_trait]
+impl FruitRepository for SqliteFruitRepository {
+    async fn list(&self) -> Result<Vec<Fruit>, FruitStoreError> {
+        self.run(|connection| {
+            let mut statement =
+                connection.prepare("SELECT id, name, nutrients FROM fruits ORDER BY rowid")?;
+            let fruits = statement
+                .query_map([], fruit_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(fruits)
+        })
+        .await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Fruit>, FruitStoreError> {
+        self.run(move |connection| select_fruit(connection, id))
+            .await
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Fruit>, FruitStoreError> {
+        let name = name.to_string();
+        self.run(move |connection| {
+            Ok(connection
+                .query_row(
+                    "SELECT id, name, nutrients FROM fruits WHERE name = ?1",
+                    params![name],
+                    fruit_from_row,
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    async fn insert(&self, fruit: Fruit) -> Result<Fruit, FruitStoreError> {
+        self.run(move |connection| {
+            insert_fruit(connection, &fruit)?;
+            Ok(fruit)
+        })
+        .await
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        update: FruitUpdate,
+    ) -> Result<Option<Fruit>, FruitStoreError> {
+        self.run(move |connection| {
+            let transaction = connection.transaction()?;
+            let Some(mut fruit) = select_fruit(&transaction, id)? else {
+                return Ok(None);
+            };
+            update.apply(&mut fruit);
+            transaction
+                .execute(
+                    "UPDATE fruits SET name = ?2, nutrients = ?3 WHERE id = ?1",
+                    params![id.to_string(), fruit.name, nutrients_json(&fruit)?],
+                )
+                .map_err(|e| {
+                    if is_unique_violation(&e) {
+                        FruitStoreError::DuplicateName(fruit.name.clone())
+                    } else {
+                        e.into()
+                    }
+                })?;
+            transaction.commit()?;
+            Ok(Some(fruit))
+        })
+        .await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, FruitStoreError> {
+        self.run(move |connection| {
+            let deleted =
+                connection.execute("DELETE FROM fruits WHERE id = ?1", params![id.to_string()])?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory() -> SqliteFruitRepository {
+        SqliteFruitRepository::init(Connection::open_in_memory().unwrap(), ":memory:").unwrap()
+    }
+
+    fn names(fruits: &[Fruit]) -> Vec<&str> {
+        fruits.iter().map(|fruit| fruit.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn sqlite_repository_crud() {
+        let repository = in_memory();
+        // a new database starts out with the sample fruits, in insertion order
+        let fruits = repository.list().await.unwrap();
+        assert_eq!(names(&fruits), ["banana", "apple", "orange"]);
+
+        let kiwi = Fruit::new(
+            "kiwi".to_string(),
+            vec![Nutrient::from_name("vitamin_c").unwrap()],
+        );
+        repository.insert(kiwi.clone()).await.unwrap();
+        let stored = repository.get(kiwi.id).await.unwrap().unwrap();
+        assert_eq!(stored.name, "kiwi");
+        assert_eq!(stored.nutrients, kiwi.nutrients);
+        let by_name = repository.find_by_name("kiwi").await.unwrap().unwrap();
+        assert_eq!(by_name.id, kiwi.id);
+        assert!(repository.find_by_name("Kiwi").await.unwrap().is_none());
+
+        let renamed = repository
+            .update(
+                kiwi.id,
+                FruitUpdate {
+                    name: Some("golden kiwi".to_string()),
+                    nutrients: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(renamed.id, kiwi.id);
+        assert_eq!(renamed.nutrients, kiwi.nutrients);
+        assert_eq!(
+            repository.get(kiwi.id).await.unwrap().unwrap().name,
+            "golden kiwi"
+        );
+        let cleared = FruitUpdate {
+            name: None,
+            nutrients: Some(Vec::new()),
+        };
+        assert!(repository
+            .update(Uuid::new_v4(), cleared)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(repository.delete(kiwi.id).await.unwrap());
+        assert!(!repository.delete(kiwi.id).await.unwrap());
+        assert!(repository.get(kiwi.id).await.unwrap().is_none());
+        assert_eq!(repository.list().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn sqlite_repository_rejects_duplicate_names() {
+        let repository = in_memory();
+        let error = repository
+            .insert(Fruit::new("apple".to_string(), Vec::new()))
+            .await
+            .unwrap_err();
+        assert!(matches!(&error, FruitStoreError::DuplicateName(name) if name == "apple"));
+        assert!(matches!(
+            ApiError::from(error),
+            ApiError::Conflict { field: "name", .. }
+        ));
+
+        let banana = repository.find_by_name("banana").await.unwrap().unwrap();
+        let error = repository
+            .update(
+                banana.id,
+                FruitUpdate {
+                    name: Some("orange".to_string()),
+                    nutrients: None,
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, FruitStoreError::DuplicateName(name) if name == "orange"));
+        // the failed rename left the row alone
+        let unchanged = repository.get(banana.id).await.unwrap().unwrap();
+        assert_eq!(unchanged.name, "banana");
+        assert_eq!(repository.list().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn migrates_version_1_nutrient_names() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE fruits (
+                    id TEXT PRIMARY KEY NOT NULL,
+                    name TEXT NOT NULL UNIQUE,
+                    nutrients TEXT NOT NULL
+                );
+                PRAGMA user_version = 1;",
+            )
+            .unwrap();
+        let id = Uuid::new_v4();
+        connection
+            .execute(
+                "INSERT INTO fruits (id, name, nutrients) VALUES (?1, ?2, ?3)",
+                params![
+                    id.to_string(),
+                    "papaya",
+                    r#"["vitamin C", "ascorbic acid", "folate", "unobtainium"]"#
+                ],
+            )
+            .unwrap();
+
+        let repository = SqliteFruitRepository::init(connection, ":memory:").unwrap();
+        // version 1 rows are kept as they are rather than seeded over
+        assert_eq!(names(&repository.list().await.unwrap()), ["papaya"]);
+        // the alias duplicates vitamin C and the unknown name is dropped
+        let papaya = repository.get(id).await.unwrap().unwrap();
+        assert_eq!(
+            papaya.nutrients,
+            [
+                Nutrient::from_name("vitamin_c").unwrap(),
+                Nutrient::from_name("folate").unwrap()
+            ]
+        );
+        let version: i64 = repository
+            .connection
+            .lock()
+            .unwrap()
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, FRUIT_SCHEMA_VERSION);
+    }
+}
@@ -1,70 +1,325 @@
-use axum::{debug_handler, extract::Path, Json};
-use borsh::BorshDeserialize;
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    debug_handler,
+    extract::rejection::BytesRejection,
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
 use uuid::Uuid;
 
-#[derive(Debug, serde::Serialize, BorshDeserialize)]
+use crate::{
+    normalize_nutrients, ApiError, AppState, FruitEncoding, FruitListQuery, FruitSearch, Nutrient,
+    NutrientInfo, NutrientInput, ValidQuery,
+};
+
+pub const MAX_FRUIT_NAME_LEN: usize = 64;
+
+#[derive(Clone, Debug, serde::Serialize, BorshSerialize, BorshDeserialize)]
 pub struct Fruit {
     // Singular name is more conventional
-    name: String,
-    nutrients: Vec<String>,
-    id: Option<String>,
+    pub name: String,
+    pub nutrients: Vec<Nutrient>,
+    // assigned once when the fruit is created and never changed
+    pub id: Uuid,
 }
 
 impl Fruit {
-    pub fn new(name: String, nutrients: Vec<String>) -> Self {
+    pub fn new(name: String, nutrients: Vec<Nutrient>) -> Self {
         Self {
             name,
             nutrients,
-            id: Some(Uuid::new_v4().to_string()),
+            id: Uuid::new_v4(),
         }
     }
 }
 
-#[debug_handler]
-pub async fn get_all_fruits() -> Json<Vec<Fruit>> {
-    // Return Vec for "all"
-    println!("Getting all fruits");
+// Amount per 100g in the reference unit, from USDA FoodData Central
+fn measured(id: &str, amount_per_100g: f64) -> Nutrient {
+    let info = NutrientInfo::lookup(id).expect("sample nutrients are in the reference table");
+    Nutrient::new(info, Some(amount_per_100g))
+}
 
-    let fruits = vec![
+// The catalog every store starts out with
+pub fn default_fruits() -> Vec<Fruit> {
+    vec![
         Fruit::new(
             "banana".to_string(),
-            vec!["potassium".to_string(), "vitamin B6".to_string()],
+            vec![measured("potassium", 358.0), measured("vitamin_b6", 0.367)],
         ),
         Fruit::new(
             "apple".to_string(),
-            vec!["fiber".to_string(), "vitamin C".to_string()],
+            vec![measured("fiber", 2.4), measured("vitamin_c", 4.6)],
         ),
         Fruit::new(
             "orange".to_string(),
-            vec!["vitamin C".to_string(), "folate".to_string()],
+            vec![measured("vitamin_c", 53.2), measured("folate", 30.0)],
         ),
-    ];
+    ]
+}
+
+// Fruit as it was serialized before nutrients were structured, down to the optional string id
+// older Borsh clients decode
+#[derive(Debug, serde::Serialize, BorshSerialize, BorshDeserialize)]
+pub struct LegacyFruit {
+    pub name: String,
+    pub nutrients: Vec<String>,
+    pub id: Option<String>,
+}
+
+impl From<Fruit> for LegacyFruit {
+    fn from(fruit: Fruit) -> Self {
+        LegacyFruit {
+            name: fruit.name,
+            nutrients: fruit
+                .nutrients
+                .into_iter()
+                .map(|nutrient| nutrient.name)
+                .collect(),
+            id: Some(fruit.id.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(untagged)]
+pub enum FruitRepresentation {
+    Current(Fruit),
+    Legacy(LegacyFruit),
+}
+
+impl FruitRepresentation {
+    pub fn new(fruit: Fruit, legacy_nutrients: bool) -> Self {
+        if legacy_nutrients {
+            FruitRepresentation::Legacy(fruit.into())
+        } else {
+            FruitRepresentation::Current(fruit)
+        }
+    }
+}
+
+// Query accepted by every endpoint that returns a single fruit
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct FruitFormatQuery {
+    // list nutrients as bare names, for clients written before they had ids
+    #[serde(default)]
+    pub legacy_nutrients: bool,
+}
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_FRUIT_NAME_LEN {
+        return Err(ApiError::Validation {
+            field: "name",
+            reason: format!("must be between 1 and {} characters", MAX_FRUIT_NAME_LEN),
+        });
+    }
+    Ok(name.to_string())
+}
+
+// Body of POST /fruits and PUT /fruit/{id}
+#[derive(Debug, PartialEq, serde::Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct FruitRequest {
+    pub name: String,
+    #[serde(default)]
+    pub nutrients: Vec<NutrientInput>,
+}
+
+// Body of PATCH /fruit/{id}; absent fields are left as they are
+#[derive(Debug, PartialEq, Default, serde::Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct FruitPatch {
+    pub name: Option<String>,
+    pub nutrients: Option<Vec<NutrientInput>>,
+}
+
+impl From<FruitRequest> for FruitPatch {
+    fn from(request: FruitRequest) -> Self {
+        FruitPatch {
+            name: Some(request.name),
+            nutrients: Some(request.nutrients),
+        }
+    }
+}
+
+// A validated FruitPatch, as handed to the repository
+#[derive(Clone, Debug, Default)]
+pub struct FruitUpdate {
+    pub name: Option<String>,
+    pub nutrients: Option<Vec<Nutrient>>,
+}
+
+impl FruitUpdate {
+    fn validate(patch: FruitPatch) -> Result<Self, ApiError> {
+        Ok(FruitUpdate {
+            name: patch.name.as_deref().map(validate_name).transpose()?,
+            nutrients: patch.nutrients.map(normalize_nutrients).transpose()?,
+        })
+    }
+
+    pub fn apply(self, fruit: &mut Fruit) {
+        if let Some(name) = self.name {
+            fruit.name = name;
+        }
+        if let Some(nutrients) = self.nutrients {
+            fruit.nutrients = nutrients;
+        }
+    }
+}
+
+fn parse_fruit_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|e| ApiError::InvalidPathParameter {
+        name: "id",
+        value: id.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+fn fruit_not_found(id: String) -> ApiError {
+    ApiError::NotFound {
+        resource: "fruit",
+        id,
+    }
+}
 
-    Json(fruits)
+// Bodies of the write endpoints may be JSON or Borsh, as their Content-Type says
+fn read_body<T>(headers: &HeaderMap, body: Result<Bytes, BytesRejection>) -> Result<T, ApiError>
+where
+    T: serde::de::DeserializeOwned + BorshDeserialize,
+{
+    let body = body.map_err(|e| ApiError::InvalidBody {
+        reason: e.body_text(),
+    })?;
+    FruitEncoding::decode(headers, &body)
 }
 
 #[debug_handler]
-pub async fn get_single_fruit(Path(fruit_name): Path<String>) -> Json<Fruit> {
+pub async fn get_all_fruits(
+    ValidQuery(query): ValidQuery<FruitListQuery>,
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let search = FruitSearch::parse(query)?;
+    println!("Getting fruits matching {:?}", search);
+
+    let page = search.page(app_state.fruits.list().await?);
+    FruitEncoding::from_accept(&request_headers).render(StatusCode::OK, &page)
+}
+
+// Accepts the fruit's id, or its name as before ids were stable
+#[debug_handler]
+pub async fn get_single_fruit(
+    Path(id_or_name): Path<String>,
+    ValidQuery(format): ValidQuery<FruitFormatQuery>,
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+) -> Result<Response, ApiError> {
     println!("Getting single fruit");
 
-    let all_fruits = vec![
-        Fruit::new(
-            "banana".to_string(),
-            vec!["potassium".to_string(), "vitamin B6".to_string()],
-        ),
-        Fruit::new(
-            "apple".to_string(),
-            vec!["fiber".to_string(), "vitamin C".to_string()],
-        ),
-        Fruit::new(
-            "orange".to_string(),
-            vec!["vitamin C".to_string(), "folate".to_string()],
-        ),
-    ];
+    let fruit = match Uuid::parse_str(&id_or_name) {
+        Ok(id) => app_state.fruits.get(id).await?,
+        Err(_) => app_state.fruits.find_by_name(&id_or_name).await?,
+    };
+
+    let fruit = fruit.ok_or_else(|| fruit_not_found(id_or_name))?;
+    FruitEncoding::from_accept(&request_headers).render(
+        StatusCode::OK,
+        &FruitRepresentation::new(fruit, format.legacy_nutrients),
+    )
+}
+
+#[debug_handler]
+pub async fn create_fruit(
+    ValidQuery(format): ValidQuery<FruitFormatQuery>,
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let request: FruitRequest = read_body(&request_headers, body)?;
+    let fruit = Fruit::new(
+        validate_name(&request.name)?,
+        normalize_nutrients(request.nutrients)?,
+    );
+    println!("Creating fruit {}", fruit.name);
 
-    let fruit = all_fruits
-        .into_iter()
-        .find(|fruit| fruit.name == fruit_name);
+    let fruit = app_state.fruits.insert(fruit).await?;
+    let location = format!("/fruit/{}", fruit.id);
+    let mut response = FruitEncoding::from_accept(&request_headers).render(
+        StatusCode::CREATED,
+        &FruitRepresentation::new(fruit, format.legacy_nutrients),
+    )?;
+    if let Ok(location) = HeaderValue::from_str(&location) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    Ok(response)
+}
+
+async fn update_fruit_with(
+    app_state: &AppState,
+    id_str: String,
+    format: FruitFormatQuery,
+    request_headers: &HeaderMap,
+    patch: FruitPatch,
+) -> Result<Response, ApiError> {
+    let id = parse_fruit_id(&id_str)?;
+    let update = FruitUpdate::validate(patch)?;
+    println!("Updating fruit {}", id);
+
+    let fruit = app_state
+        .fruits
+        .update(id, update)
+        .await?
+        .ok_or_else(|| fruit_not_found(id_str))?;
+    FruitEncoding::from_accept(request_headers).render(
+        StatusCode::OK,
+        &FruitRepresentation::new(fruit, format.legacy_nutrients),
+    )
+}
 
-    fruit.map(Json).unwrap()
+#[debug_handler]
+pub async fn replace_fruit(
+    Path(id_str): Path<String>,
+    ValidQuery(format): ValidQuery<FruitFormatQuery>,
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let request: FruitRequest = read_body(&request_headers, body)?;
+    update_fruit_with(
+        &app_state,
+        id_str,
+        format,
+        &request_headers,
+        FruitPatch::from(request),
+    )
+    .await
+}
+
+#[debug_handler]
+pub async fn update_fruit(
+    Path(id_str): Path<String>,
+    ValidQuery(format): ValidQuery<FruitFormatQuery>,
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let patch = read_body(&request_headers, body)?;
+    update_fruit_with(&app_state, id_str, format, &request_headers, patch).await
+}
+
+#[debug_handler]
+pub async fn delete_fruit(
+    Path(id_str): Path<String>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<StatusCode, ApiError> {
+    let id = parse_fruit_id(&id_str)?;
+    println!("Deleting fruit {}", id);
+
+    if app_state.fruits.delete(id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(fruit_not_found(id_str))
+    }
 }
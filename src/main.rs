@@ -1,49 +1,202 @@
-use std::{str::FromStr, sync::Arc};
+use std::sync::Arc;
 
 use axum::{
     routing::{get, post},
     Router,
 };
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 
+pub mod account_source;
+pub use account_source::*;
+pub mod config;
+pub use config::*;
+pub mod conjunctions;
+pub use conjunctions::*;
+pub mod error;
+pub use error::*;
+pub mod fruit_encoding;
+pub use fruit_encoding::*;
+pub mod fruit_search;
+pub use fruit_search::*;
+pub mod fruit_store;
+pub use fruit_store::*;
 pub mod fruits;
+pub mod nutrients;
+pub use nutrients::*;
+pub mod orbit;
+pub use orbit::*;
+pub mod passes;
+pub use passes::*;
 pub mod satellite;
 pub use fruits::*;
 pub use satellite::*;
+pub mod rpc;
+pub use rpc::*;
+pub mod satellite_cache;
+pub use satellite_cache::*;
+pub mod satellite_events;
+pub use satellite_events::*;
+pub mod satellite_history;
+pub use satellite_history::*;
+pub mod satellite_stream;
+pub use satellite_stream::*;
+pub mod satellite_batch;
+pub use satellite_batch::*;
+pub mod satellite_transactions;
+pub use satellite_transactions::*;
+pub mod tle;
+pub use tle::*;
+pub mod upstream;
+pub use upstream::*;
 pub mod generate_keypair;
 pub use generate_keypair::*;
+#[cfg(test)]
+mod router_tests;
 
 pub struct AppState {
     pub program_id: Pubkey,
-    pub rpc_client: Arc<RpcClient>,
+    pub account_source: Arc<dyn AccountSource>,
+    pub satellite_cache: SatelliteCache,
+    pub satellite_streams: SatelliteStreams,
+    pub satellite_events: SatelliteEvents,
+    pub fruits: Arc<dyn FruitRepository>,
+    pub config: AppConfig,
 }
 
 #[shuttle_runtime::main]
-async fn main() -> shuttle_axum::ShuttleAxum {
-    // configure rpc client
-    let rpc_url = "https://api.devnet.solana.com".to_string();
-    let rpc_client = Arc::new(RpcClient::new(rpc_url));
+async fn main(
+    #[shuttle_runtime::Secrets] secrets: shuttle_runtime::SecretStore,
+) -> shuttle_axum::ShuttleAxum {
+    // load and validate configuration before touching the network
+    let config = AppConfig::load(&secrets).map_err(|e| {
+        eprintln!("Invalid configuration: {}", e);
+        shuttle_runtime::CustomError::new(e)
+    })?;
+    println!(
+        "Using cluster {} at {} (ws {}) with program {}",
+        config.cluster, config.rpc_url, config.ws_url, config.program_id
+    );
 
-    // configure program id
-    let program_id_str = "FZQmSamSJdtB9JKxbUH82ZdRQ2UcqqBPGbyce2ZdfviN".to_string();
-    let program_id = Pubkey::from_str(&program_id_str).unwrap();
+    // configure where accounts are read from
+    let account_source: Arc<dyn AccountSource> = match config.account_source {
+        AccountSourceKind::Rpc => Arc::new(rpc_client(
+            config.rpc_url.clone(),
+            config.request_timeout,
+            config.commitment,
+        )),
+        AccountSourceKind::Memory => {
+            let account_dir = config.account_dir.clone().unwrap_or_default();
+            let source = InMemoryAccountSource::from_account_dir(&account_dir).map_err(|e| {
+                eprintln!(
+                    "Failed to load accounts from {}: {}",
+                    account_dir.display(),
+                    e
+                );
+                shuttle_runtime::CustomError::msg(e)
+            })?;
+            if source.is_empty() {
+                eprintln!("No accounts found in {}", account_dir.display());
+            }
+            println!(
+                "Serving {} in-memory accounts from {}",
+                source.len(),
+                account_dir.display()
+            );
+            Arc::new(source)
+        }
+    };
+
+    let fruits: Arc<dyn FruitRepository> = match config.fruit_store {
+        FruitStoreKind::Memory => Arc::new(InMemoryFruitRepository::with_default_fruits()),
+        FruitStoreKind::Sqlite => {
+            let repository = SqliteFruitRepository::open(&config.fruit_db_path).map_err(|e| {
+                eprintln!(
+                    "Failed to open fruit database {}: {}",
+                    config.fruit_db_path.display(),
+                    e
+                );
+                shuttle_runtime::CustomError::new(e)
+            })?;
+            println!("Storing fruits in {}", config.fruit_db_path.display());
+            Arc::new(repository)
+        }
+    };
 
     let app_state = Arc::new(AppState {
-        program_id,
-        rpc_client,
+        program_id: config.program_id,
+        account_source,
+        satellite_cache: SatelliteCache::new(config.cache_ttl, SATELLITE_CACHE_MAX_ENTRIES),
+        satellite_streams: SatelliteStreams::new(STREAM_CHANNEL_CAPACITY),
+        satellite_events: SatelliteEvents::new(EVENT_CHANNEL_CAPACITY),
+        fruits,
+        config,
     });
 
-    // create the router
-    let router = Router::new()
-        .route("/fruits", get(get_all_fruits))
-        .route("/fruit/{name}", get(get_single_fruit))
+    Ok(router(app_state).into())
+}
+
+// create the router; kept separate from main so it can be driven with any AccountSource
+pub fn router(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/fruits", get(get_all_fruits).post(create_fruit))
+        .route(
+            "/fruit/{id}",
+            get(get_single_fruit)
+                .put(replace_fruit)
+                .patch(update_fruit)
+                .delete(delete_fruit),
+        )
+        .route("/nutrients", get(get_nutrients))
+        .route("/satellites", get(get_all_satellites))
+        .route(
+            "/satellites/transactions/create",
+            post(create_satellite_transaction),
+        )
+        .route("/satellites/events", get(get_satellite_events))
+        .route("/satellites/batch", post(get_satellites_batch))
+        .route("/satellites/tle", post(import_tle))
+        .route(
+            "/satellites/pda/{user_authority}/{registry_authority}/{norad_id}",
+            get(get_satellite_pda),
+        )
         .route(
             "/satellites/{user_authority}/{registry_authority}/{norad_id}",
             get(get_satellite_from_norad_id),
         )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/position",
+            get(get_satellite_position),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/passes",
+            get(get_satellite_passes),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/ground_track",
+            get(get_satellite_ground_track),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/conjunctions",
+            get(get_satellite_conjunctions),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/history",
+            get(get_satellite_history),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/stream",
+            get(stream_satellite),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/status",
+            post(update_status_transaction),
+        )
+        .route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}/maneuvers",
+            post(record_maneuver_transaction),
+        )
         .route("/keypair", post(generate_keypair))
-        .with_state(app_state);
-
-    Ok(router.into())
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed)
+        .with_state(app_state)
 }
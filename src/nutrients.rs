@@ -0,0 +1,356 @@
+use axum::{debug_handler, Json};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::ApiError;
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub enum NutrientUnit {
+    #[serde(rename = "g")]
+    Gram,
+    #[serde(rename = "mg")]
+    Milligram,
+    #[serde(rename = "mcg")]
+    Microgram,
+}
+
+impl NutrientUnit {
+    fn grams(&self) -> f64 {
+        match self {
+            NutrientUnit::Gram => 1.0,
+            NutrientUnit::Milligram => 1e-3,
+            NutrientUnit::Microgram => 1e-6,
+        }
+    }
+
+    pub fn convert(&self, amount: f64, to: NutrientUnit) -> f64 {
+        amount * self.grams() / to.grams()
+    }
+}
+
+// One row of the nutrient reference table
+#[derive(Debug, serde::Serialize)]
+pub struct NutrientInfo {
+    // canonical id fruits refer to the nutrient by
+    pub id: &'static str,
+    // also the string the nutrient was listed as before nutrients had ids
+    pub name: &'static str,
+    pub unit: NutrientUnit,
+    // FDA daily value for adults, in `unit`
+    pub daily_value: f64,
+    // other spellings accepted on input
+    pub aliases: &'static [&'static str],
+}
+
+pub const NUTRIENTS: &[NutrientInfo] = &[
+    NutrientInfo {
+        id: "calcium",
+        name: "calcium",
+        unit: NutrientUnit::Milligram,
+        daily_value: 1300.0,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "copper",
+        name: "copper",
+        unit: NutrientUnit::Milligram,
+        daily_value: 0.9,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "fiber",
+        name: "fiber",
+        unit: NutrientUnit::Gram,
+        daily_value: 28.0,
+        aliases: &["fibre", "dietary fiber", "dietary fibre"],
+    },
+    NutrientInfo {
+        id: "folate",
+        name: "folate",
+        unit: NutrientUnit::Microgram,
+        daily_value: 400.0,
+        aliases: &["folic acid", "vitamin b9"],
+    },
+    NutrientInfo {
+        id: "iron",
+        name: "iron",
+        unit: NutrientUnit::Milligram,
+        daily_value: 18.0,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "magnesium",
+        name: "magnesium",
+        unit: NutrientUnit::Milligram,
+        daily_value: 420.0,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "manganese",
+        name: "manganese",
+        unit: NutrientUnit::Milligram,
+        daily_value: 2.3,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "potassium",
+        name: "potassium",
+        unit: NutrientUnit::Milligram,
+        daily_value: 4700.0,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "protein",
+        name: "protein",
+        unit: NutrientUnit::Gram,
+        daily_value: 50.0,
+        aliases: &[],
+    },
+    NutrientInfo {
+        id: "vitamin_a",
+        name: "vitamin A",
+        unit: NutrientUnit::Microgram,
+        daily_value: 900.0,
+        aliases: &["retinol"],
+    },
+    NutrientInfo {
+        id: "vitamin_b6",
+        name: "vitamin B6",
+        unit: NutrientUnit::Milligram,
+        daily_value: 1.7,
+        aliases: &["pyridoxine"],
+    },
+    NutrientInfo {
+        id: "vitamin_c",
+        name: "vitamin C",
+        unit: NutrientUnit::Milligram,
+        daily_value: 90.0,
+        aliases: &["ascorbic acid"],
+    },
+    NutrientInfo {
+        id: "vitamin_e",
+        name: "vitamin E",
+        unit: NutrientUnit::Milligram,
+        daily_value: 15.0,
+        aliases: &["tocopherol"],
+    },
+    NutrientInfo {
+        id: "vitamin_k",
+        name: "vitamin K",
+        unit: NutrientUnit::Microgram,
+        daily_value: 120.0,
+        aliases: &["phylloquinone"],
+    },
+];
+
+// "Vitamin C", "vitamin-c" and "vitamin_c" all become "vitamin_c"
+fn normalize_key(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+impl NutrientInfo {
+    // Looks a nutrient up by its id, name or any alias, ignoring case and separators
+    pub fn lookup(name: &str) -> Option<&'static NutrientInfo> {
+        let key = normalize_key(name);
+        NUTRIENTS.iter().find(|info| {
+            info.id == key
+                || normalize_key(info.name) == key
+                || info.aliases.iter().any(|alias| normalize_key(alias) == key)
+        })
+    }
+}
+
+#[derive(
+    Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub struct Nutrient {
+    pub id: String,
+    pub name: String,
+    pub unit: NutrientUnit,
+    // None when the fruit is only known to contain the nutrient
+    pub amount_per_100g: Option<f64>,
+    // share of the daily value in 100g, rounded to a tenth of a percent
+    pub daily_value_percent: Option<f64>,
+}
+
+impl Nutrient {
+    // amount is in the reference unit of the nutrient
+    pub fn new(info: &NutrientInfo, amount_per_100g: Option<f64>) -> Self {
+        Nutrient {
+            id: info.id.to_string(),
+            name: info.name.to_string(),
+            unit: info.unit,
+            amount_per_100g,
+            daily_value_percent: amount_per_100g
+                .map(|amount| (amount / info.daily_value * 1000.0).round() / 10.0),
+        }
+    }
+
+    // The bare name nutrients were listed under before they were structured
+    pub fn from_name(name: &str) -> Option<Self> {
+        NutrientInfo::lookup(name).map(|info| Nutrient::new(info, None))
+    }
+}
+
+// How a nutrient can be given in a request: a bare name as before, or an object with an amount
+// In Borsh the variant is told apart by its tag
+#[derive(Debug, PartialEq, serde::Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(untagged)]
+pub enum NutrientInput {
+    Name(String),
+    Measured {
+        // canonical id, name or alias
+        id: String,
+        amount_per_100g: Option<f64>,
+        // defaults to the reference unit of the nutrient
+        unit: Option<NutrientUnit>,
+    },
+}
+
+fn invalid_nutrients(reason: String) -> ApiError {
+    ApiError::Validation {
+        field: "nutrients",
+        reason,
+    }
+}
+
+// Resolves every input against the reference table, converting amounts to its units
+pub fn normalize_nutrients(inputs: Vec<NutrientInput>) -> Result<Vec<Nutrient>, ApiError> {
+    let mut nutrients: Vec<Nutrient> = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let (name, amount, unit) = match input {
+            NutrientInput::Name(name) => (name, None, None),
+            NutrientInput::Measured {
+                id,
+                amount_per_100g,
+                unit,
+            } => (id, amount_per_100g, unit),
+        };
+        let info = NutrientInfo::lookup(&name).ok_or_else(|| {
+            invalid_nutrients(format!(
+                "unknown nutrient '{}'; see GET /nutrients for the accepted ids",
+                name.trim()
+            ))
+        })?;
+        if let Some(amount) = amount {
+            if !amount.is_finite() || amount < 0.0 {
+                return Err(invalid_nutrients(format!(
+                    "amount_per_100g of {} must be a non-negative number",
+                    info.id
+                )));
+            }
+        }
+        if nutrients.iter().any(|nutrient| nutrient.id == info.id) {
+            return Err(invalid_nutrients(format!(
+                "{} is listed more than once",
+                info.id
+            )));
+        }
+        // converted amounts are rounded so they don't carry float noise like 40.300000000000004
+        let amount = match (amount, unit) {
+            (Some(amount), Some(unit)) if unit != info.unit => {
+                Some((unit.convert(amount, info.unit) * 1e4).round() / 1e4)
+            }
+            _ => amount,
+        };
+        nutrients.push(Nutrient::new(info, amount));
+    }
+    Ok(nutrients)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct NutrientListApiResponse {
+    pub nutrients: &'static [NutrientInfo],
+}
+
+#[debug_handler]
+pub async fn get_nutrients() -> Json<NutrientListApiResponse> {
+    println!("Getting nutrient reference table");
+    Json(NutrientListApiResponse {
+        nutrients: NUTRIENTS,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured(id: &str, amount: f64, unit: Option<NutrientUnit>) -> NutrientInput {
+        NutrientInput::Measured {
+            id: id.to_string(),
+            amount_per_100g: Some(amount),
+            unit,
+        }
+    }
+
+    #[test]
+    fn lookup_accepts_ids_names_and_aliases() {
+        for name in [
+            "vitamin_c",
+            "vitamin C",
+            "Vitamin-C",
+            "ascorbic acid",
+            " Ascorbic_Acid ",
+        ] {
+            assert_eq!(
+                NutrientInfo::lookup(name).unwrap().id,
+                "vitamin_c",
+                "{}",
+                name
+            );
+        }
+        assert_eq!(NutrientInfo::lookup("dietary fibre").unwrap().id, "fiber");
+        assert!(NutrientInfo::lookup("unobtainium").is_none());
+    }
+
+    #[test]
+    fn normalize_converts_amounts_to_reference_units() {
+        let nutrients = normalize_nutrients(vec![
+            // vitamin C is referenced in mg
+            measured("ascorbic acid", 0.0403, Some(NutrientUnit::Gram)),
+            // folate in mcg
+            measured("folate", 0.014, Some(NutrientUnit::Milligram)),
+            // no unit means the reference unit already
+            measured("potassium", 358.0, None),
+            NutrientInput::Name("fibre".to_string()),
+        ])
+        .unwrap();
+
+        assert_eq!(nutrients[0].id, "vitamin_c");
+        assert_eq!(nutrients[0].unit, NutrientUnit::Milligram);
+        assert_eq!(nutrients[0].amount_per_100g, Some(40.3));
+        assert_eq!(nutrients[0].daily_value_percent, Some(44.8));
+        assert_eq!(nutrients[1].id, "folate");
+        assert_eq!(nutrients[1].amount_per_100g, Some(14.0));
+        assert_eq!(nutrients[2].amount_per_100g, Some(358.0));
+        assert_eq!(nutrients[3], Nutrient::from_name("fiber").unwrap());
+    }
+
+    #[test]
+    fn normalize_rejects_duplicates_and_bad_amounts() {
+        let duplicate = normalize_nutrients(vec![
+            NutrientInput::Name("vitamin C".to_string()),
+            measured("ascorbic acid", 10.0, None),
+        ]);
+        assert!(matches!(
+            duplicate,
+            Err(ApiError::Validation { field: "nutrients", reason }) if reason.contains("vitamin_c")
+        ));
+        assert!(normalize_nutrients(vec![measured("potassium", -1.0, None)]).is_err());
+        assert!(normalize_nutrients(vec![measured("potassium", f64::NAN, None)]).is_err());
+        assert!(normalize_nutrients(vec![NutrientInput::Name("unobtainium".to_string())]).is_err());
+    }
+}
@@ -0,0 +1,619 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use axum::{debug_handler, extract::State, Json};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    fetch_satellite, ApiError, AppState, SatelliteApiResponse, SatellitePath, SatelliteSeeds,
+    ValidQuery,
+};
+
+// Earth constants (WGS84 / EGM96), distances in km and times in seconds
+pub const EARTH_MU: f64 = 398_600.441_8;
+pub const EARTH_EQUATORIAL_RADIUS: f64 = 6_378.137;
+pub const EARTH_FLATTENING: f64 = 1.0 / 298.257_223_563;
+pub const EARTH_J2: f64 = 1.082_626_68e-3;
+pub const EARTH_ROTATION_RATE: f64 = 7.292_115_0e-5;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn sub(&self, other: &Vector3) -> Vector3 {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+
+    pub fn dot(&self, other: &Vector3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    // rotation about the z axis by `angle` radians
+    pub fn rotate_z(&self, angle: f64) -> Vector3 {
+        let (sin, cos) = angle.sin_cos();
+        Vector3::new(
+            cos * self.x - sin * self.y,
+            sin * self.x + cos * self.y,
+            self.z,
+        )
+    }
+
+    // rotation about the x axis by `angle` radians
+    pub fn rotate_x(&self, angle: f64) -> Vector3 {
+        let (sin, cos) = angle.sin_cos();
+        Vector3::new(
+            self.x,
+            cos * self.y - sin * self.z,
+            sin * self.y + cos * self.z,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropagationModel {
+    TwoBody,
+    // secular drift of RAAN, argument of periapsis and mean anomaly from Earth's oblateness
+    J2,
+}
+
+// The registry stores no mean anomaly or element epoch, so the satellite is taken to be at
+// periapsis (mean anomaly 0) at its launch date. Angles are stored in degrees, lengths in km.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeplerianElements {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub raan: f64,
+    pub arg_of_periapsis: f64,
+    pub mean_anomaly: f64,
+    pub epoch: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct StateVector {
+    pub position: Vector3,
+    pub velocity: Vector3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct Geodetic {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+impl KeplerianElements {
+    pub fn from_satellite(satellite: &SatelliteApiResponse) -> Result<Self, ApiError> {
+        let elements = KeplerianElements {
+            semi_major_axis: satellite.semi_major_axis,
+            eccentricity: satellite.eccentricity,
+            inclination: satellite.inclination,
+            raan: satellite.raan,
+            arg_of_periapsis: satellite.arg_of_periapsis,
+            mean_anomaly: 0.0,
+            epoch: satellite.launch_date,
+        };
+        elements.validate()?;
+        Ok(elements)
+    }
+
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let invalid = |reason: String| Err(ApiError::InvalidOrbit { reason });
+        let values = [
+            self.semi_major_axis,
+            self.eccentricity,
+            self.inclination,
+            self.raan,
+            self.arg_of_periapsis,
+            self.mean_anomaly,
+        ];
+        if values.iter().any(|value| !value.is_finite()) {
+            return invalid("orbital elements must be finite numbers".to_string());
+        }
+        if !(0.0..1.0).contains(&self.eccentricity) {
+            return invalid(format!(
+                "eccentricity {} is not an elliptical orbit (0 <= e < 1)",
+                self.eccentricity
+            ));
+        }
+        let periapsis_radius = self.semi_major_axis * (1.0 - self.eccentricity);
+        if periapsis_radius <= EARTH_EQUATORIAL_RADIUS {
+            return invalid(format!(
+                "periapsis radius {:.1} km with semi-major axis {} km is inside the Earth",
+                periapsis_radius, self.semi_major_axis
+            ));
+        }
+        Ok(())
+    }
+
+    // rad/s
+    pub fn mean_motion(&self) -> f64 {
+        (EARTH_MU / self.semi_major_axis.powi(3)).sqrt()
+    }
+
+    // seconds
+    pub fn period(&self) -> f64 {
+        2.0 * PI / self.mean_motion()
+    }
+
+    // Secular rates (rad/s) of RAAN, argument of periapsis and mean anomaly
+    fn rates(&self, model: PropagationModel) -> (f64, f64, f64) {
+        let n = self.mean_motion();
+        match model {
+            PropagationModel::TwoBody => (0.0, 0.0, n),
+            PropagationModel::J2 => {
+                let e2 = self.eccentricity * self.eccentricity;
+                let p = self.semi_major_axis * (1.0 - e2);
+                let factor = 1.5 * n * EARTH_J2 * (EARTH_EQUATORIAL_RADIUS / p).powi(2);
+                let sin_i2 = self.inclination.to_radians().sin().powi(2);
+                let cos_i = self.inclination.to_radians().cos();
+                (
+                    -factor * cos_i,
+                    factor * (2.0 - 2.5 * sin_i2),
+                    n + factor * (1.0 - e2).sqrt() * (1.0 - 1.5 * sin_i2),
+                )
+            }
+        }
+    }
+
+    // Position (km) and velocity (km/s) in the inertial frame at a unix time in seconds
+    pub fn propagate(&self, model: PropagationModel, unix_time: f64) -> StateVector {
+        let dt = unix_time - self.epoch as f64;
+        let (raan_rate, arg_of_periapsis_rate, mean_anomaly_rate) = self.rates(model);
+
+        let raan = self.raan.to_radians() + raan_rate * dt;
+        let arg_of_periapsis = self.arg_of_periapsis.to_radians() + arg_of_periapsis_rate * dt;
+        let mean_anomaly =
+            (self.mean_anomaly.to_radians() + mean_anomaly_rate * dt).rem_euclid(2.0 * PI);
+
+        let e = self.eccentricity;
+        let eccentric_anomaly = solve_kepler(mean_anomaly, e);
+        let true_anomaly = 2.0
+            * ((1.0 + e).sqrt() * (eccentric_anomaly / 2.0).sin())
+                .atan2((1.0 - e).sqrt() * (eccentric_anomaly / 2.0).cos());
+
+        let p = self.semi_major_axis * (1.0 - e * e);
+        let radius = self.semi_major_axis * (1.0 - e * eccentric_anomaly.cos());
+        let (sin_nu, cos_nu) = true_anomaly.sin_cos();
+        let speed_factor = (EARTH_MU / p).sqrt();
+
+        // perifocal frame, then rotate by argument of periapsis, inclination and RAAN
+        let to_inertial = |vector: Vector3| {
+            vector
+                .rotate_z(arg_of_periapsis)
+                .rotate_x(self.inclination.to_radians())
+                .rotate_z(raan)
+        };
+        StateVector {
+            position: to_inertial(Vector3::new(radius * cos_nu, radius * sin_nu, 0.0)),
+            velocity: to_inertial(Vector3::new(
+                -speed_factor * sin_nu,
+                speed_factor * (e + cos_nu),
+                0.0,
+            )),
+        }
+    }
+}
+
+// Orbit regimes by altitude above the equator; anything clearly eccentric is HEO
+pub const LEO_MAX_ALTITUDE: f64 = 2_000.0;
+pub const GEO_ALTITUDE: f64 = 35_786.0;
+pub const GEO_ALTITUDE_TOLERANCE: f64 = 200.0;
+pub const HEO_MIN_ECCENTRICITY: f64 = 0.25;
+// how far the stored altitude may sit outside the periapsis-apoapsis band
+pub const ALTITUDE_TOLERANCE: f64 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrbitClass {
+    Leo,
+    Meo,
+    Geo,
+    Heo,
+}
+
+impl OrbitClass {
+    pub fn classify(semi_major_axis: f64, eccentricity: f64) -> OrbitClass {
+        let altitude = semi_major_axis - EARTH_EQUATORIAL_RADIUS;
+        if eccentricity >= HEO_MIN_ECCENTRICITY {
+            OrbitClass::Heo
+        } else if altitude < LEO_MAX_ALTITUDE {
+            OrbitClass::Leo
+        } else if (altitude - GEO_ALTITUDE).abs() <= GEO_ALTITUDE_TOLERANCE {
+            OrbitClass::Geo
+        } else if altitude < GEO_ALTITUDE {
+            OrbitClass::Meo
+        } else {
+            OrbitClass::Heo
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrbitClass::Leo => "LEO",
+            OrbitClass::Meo => "MEO",
+            OrbitClass::Geo => "GEO",
+            OrbitClass::Heo => "HEO",
+        }
+    }
+}
+
+// Values computed from the stored elements. Everything is None when the elements do not
+// describe an ellipse at all; the reason is then in `warnings`.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct DerivedOrbitalParameters {
+    pub period_secs: Option<f64>,
+    pub mean_motion_rev_per_day: Option<f64>,
+    // km above the equatorial radius
+    pub apoapsis_altitude: Option<f64>,
+    pub periapsis_altitude: Option<f64>,
+    // km^2/s^2
+    pub specific_orbital_energy: Option<f64>,
+    pub orbit_class: Option<OrbitClass>,
+    // stored altitude lies between periapsis and apoapsis altitude
+    pub altitude_consistent: Option<bool>,
+    // stored orbit_type names the class the elements fall into
+    pub orbit_type_consistent: Option<bool>,
+    pub warnings: Vec<String>,
+}
+
+impl DerivedOrbitalParameters {
+    pub fn from_satellite(satellite: &SatelliteApiResponse) -> Self {
+        let a = satellite.semi_major_axis;
+        let e = satellite.eccentricity;
+        if !a.is_finite() || !e.is_finite() || a <= 0.0 || !(0.0..1.0).contains(&e) {
+            return DerivedOrbitalParameters {
+                warnings: vec![format!(
+                    "semi_major_axis {} km and eccentricity {} do not describe an elliptical orbit",
+                    a, e
+                )],
+                ..DerivedOrbitalParameters::default()
+            };
+        }
+
+        let mut warnings = Vec::new();
+        let mean_motion = (EARTH_MU / a.powi(3)).sqrt();
+        let apoapsis_altitude = a * (1.0 + e) - EARTH_EQUATORIAL_RADIUS;
+        let periapsis_altitude = a * (1.0 - e) - EARTH_EQUATORIAL_RADIUS;
+        if periapsis_altitude <= 0.0 {
+            warnings.push(format!(
+                "periapsis altitude {:.1} km is below the surface",
+                periapsis_altitude
+            ));
+        }
+
+        let altitude_consistent = (periapsis_altitude - ALTITUDE_TOLERANCE
+            ..=apoapsis_altitude + ALTITUDE_TOLERANCE)
+            .contains(&satellite.altitude);
+        if !altitude_consistent {
+            warnings.push(format!(
+                "altitude {} km is outside the periapsis-apoapsis range {:.1}-{:.1} km",
+                satellite.altitude, periapsis_altitude, apoapsis_altitude
+            ));
+        }
+
+        let orbit_class = OrbitClass::classify(a, e);
+        let orbit_type_consistent = satellite
+            .orbit_type
+            .eq_ignore_ascii_case(orbit_class.as_str());
+        if !orbit_type_consistent {
+            warnings.push(format!(
+                "orbit_type '{}' does not match the {} orbit the elements describe",
+                satellite.orbit_type,
+                orbit_class.as_str()
+            ));
+        }
+
+        DerivedOrbitalParameters {
+            period_secs: Some(2.0 * PI / mean_motion),
+            mean_motion_rev_per_day: Some(mean_motion * 86_400.0 / (2.0 * PI)),
+            apoapsis_altitude: Some(apoapsis_altitude),
+            periapsis_altitude: Some(periapsis_altitude),
+            specific_orbital_energy: Some(-EARTH_MU / (2.0 * a)),
+            orbit_class: Some(orbit_class),
+            altitude_consistent: Some(altitude_consistent),
+            orbit_type_consistent: Some(orbit_type_consistent),
+            warnings,
+        }
+    }
+}
+
+// Newton iteration on E - e sin E = M
+pub fn solve_kepler(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let mut eccentric_anomaly = if eccentricity > 0.8 { PI } else { mean_anomaly };
+    for _ in 0..50 {
+        let delta = (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+            / (1.0 - eccentricity * eccentric_anomaly.cos());
+        eccentric_anomaly -= delta;
+        if delta.abs() < 1e-12 {
+            break;
+        }
+    }
+    eccentric_anomaly
+}
+
+// Greenwich mean sidereal time in radians (IAU 1982, truncated)
+pub fn gmst(unix_time: f64) -> f64 {
+    let days_since_j2000 = unix_time / 86_400.0 + 2_440_587.5 - 2_451_545.0;
+    (280.460_618_37 + 360.985_647_366_29 * days_since_j2000)
+        .rem_euclid(360.0)
+        .to_radians()
+}
+
+pub fn eci_to_ecef(position: &Vector3, unix_time: f64) -> Vector3 {
+    position.rotate_z(-gmst(unix_time))
+}
+
+// WGS84 geodetic coordinates (degrees, km above the ellipsoid)
+pub fn ecef_to_geodetic(position: &Vector3) -> Geodetic {
+    let e2 = EARTH_FLATTENING * (2.0 - EARTH_FLATTENING);
+    let longitude = position.y.atan2(position.x);
+    let horizontal = (position.x * position.x + position.y * position.y).sqrt();
+
+    let mut latitude = position.z.atan2(horizontal * (1.0 - e2));
+    let mut altitude = 0.0;
+    for _ in 0..10 {
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+        let prime_vertical = EARTH_EQUATORIAL_RADIUS / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+        // cos(latitude) goes to 0 at the poles, so measure from the axis there instead
+        altitude = if sin_lat.abs() > cos_lat.abs() {
+            position.z / sin_lat - prime_vertical * (1.0 - e2)
+        } else {
+            horizontal / cos_lat - prime_vertical
+        };
+        let next = position
+            .z
+            .atan2(horizontal * (1.0 - e2 * prime_vertical / (prime_vertical + altitude)));
+        if (next - latitude).abs() < 1e-12 {
+            latitude = next;
+            break;
+        }
+        latitude = next;
+    }
+
+    Geodetic {
+        latitude: latitude.to_degrees(),
+        longitude: longitude.to_degrees(),
+        altitude,
+    }
+}
+
+pub fn geodetic_to_ecef(geodetic: &Geodetic) -> Vector3 {
+    let e2 = EARTH_FLATTENING * (2.0 - EARTH_FLATTENING);
+    let (sin_lat, cos_lat) = geodetic.latitude.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = geodetic.longitude.to_radians().sin_cos();
+    let prime_vertical = EARTH_EQUATORIAL_RADIUS / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+    Vector3::new(
+        (prime_vertical + geodetic.altitude) * cos_lat * cos_lon,
+        (prime_vertical + geodetic.altitude) * cos_lat * sin_lon,
+        (prime_vertical * (1.0 - e2) + geodetic.altitude) * sin_lat,
+    )
+}
+
+pub(crate) fn parse_rfc3339(field: &'static str, value: &str) -> Result<DateTime<Utc>, ApiError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| ApiError::InvalidQuery {
+            reason: format!("{} '{}' is not an RFC 3339 timestamp: {}", field, value, e),
+        })
+}
+
+pub(crate) fn unix_seconds(time: &DateTime<Utc>) -> f64 {
+    time.timestamp() as f64 + time.timestamp_subsec_nanos() as f64 * 1e-9
+}
+
+// rounded to the millisecond, which is as precise as the propagation is
+pub(crate) fn from_unix_seconds(unix_time: f64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis((unix_time * 1_000.0).round() as i64).unwrap_or_default()
+}
+
+// Look up a satellite and the elements to propagate it with
+pub(crate) async fn fetch_elements(
+    app_state: &AppState,
+    seeds: &SatelliteSeeds,
+) -> Result<(SatelliteApiResponse, KeplerianElements), ApiError> {
+    let satellite = fetch_satellite(app_state, seeds).await?.satellite.clone();
+    let elements = KeplerianElements::from_satellite(&satellite)?;
+    Ok((satellite, elements))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PositionQuery {
+    pub at: Option<String>,
+    pub model: Option<PropagationModel>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SatellitePositionApiResponse {
+    pub norad_id: u64,
+    pub at: DateTime<Utc>,
+    pub epoch: Option<DateTime<Utc>>,
+    pub model: PropagationModel,
+    // km, Earth-centred inertial (true equator, mean equinox of date)
+    pub position_eci: Vector3,
+    // km/s, Earth-centred inertial
+    pub velocity_eci: Vector3,
+    pub speed: f64,
+    pub geodetic: Geodetic,
+}
+
+#[debug_handler]
+pub async fn get_satellite_position(
+    SatellitePath(seeds): SatellitePath,
+    ValidQuery(query): ValidQuery<PositionQuery>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<SatellitePositionApiResponse>, ApiError> {
+    let at = match &query.at {
+        Some(at) => parse_rfc3339("at", at)?,
+        None => Utc::now(),
+    };
+    let model = query.model.unwrap_or(PropagationModel::TwoBody);
+
+    let (satellite, elements) = fetch_elements(&app_state, &seeds).await?;
+
+    println!(
+        "Propagating NORAD ID {} to {} with {:?}",
+        satellite.norad_id, at, model
+    );
+    let unix_time = unix_seconds(&at);
+    let state = elements.propagate(model, unix_time);
+    let geodetic = ecef_to_geodetic(&eci_to_ecef(&state.position, unix_time));
+
+    Ok(Json(SatellitePositionApiResponse {
+        norad_id: satellite.norad_id,
+        at,
+        epoch: DateTime::from_timestamp(elements.epoch, 0),
+        model,
+        position_eci: state.position,
+        velocity_eci: state.velocity,
+        speed: state.velocity.norm(),
+        geodetic,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::{satellite::tests::test_satellite, OperationStatus};
+
+    fn elements(semi_major_axis: f64, eccentricity: f64) -> KeplerianElements {
+        KeplerianElements {
+            semi_major_axis,
+            eccentricity,
+            inclination: 28.5,
+            raan: 40.0,
+            arg_of_periapsis: 60.0,
+            mean_anomaly: 10.0,
+            epoch: 1_700_000_000,
+        }
+    }
+
+    fn specific_energy(state: &StateVector) -> f64 {
+        state.velocity.dot(&state.velocity) / 2.0 - EARTH_MU / state.position.norm()
+    }
+
+    fn angular_momentum(state: &StateVector) -> Vector3 {
+        let (r, v) = (state.position, state.velocity);
+        Vector3::new(
+            r.y * v.z - r.z * v.y,
+            r.z * v.x - r.x * v.z,
+            r.x * v.y - r.y * v.x,
+        )
+    }
+
+    #[test]
+    fn circular_orbit_period_and_energy() {
+        // geostationary radius goes round once per sidereal day
+        let geo = elements(42_164.17, 0.0);
+        assert!((geo.period() - 86_164.09).abs() < 1.0);
+
+        let leo = elements(EARTH_EQUATORIAL_RADIUS + 400.0, 0.0);
+        let expected_period = 2.0 * PI * (leo.semi_major_axis.powi(3) / EARTH_MU).sqrt();
+        assert!((leo.period() - expected_period).abs() < 1e-9);
+        assert!((leo.period() / 60.0 - 92.56).abs() < 0.01);
+
+        let state = leo.propagate(PropagationModel::TwoBody, leo.epoch as f64 + 1_234.0);
+        assert!((state.position.norm() - leo.semi_major_axis).abs() < 1e-9);
+        // circular speed, and the velocity is perpendicular to the radius
+        assert!((state.velocity.norm() - (EARTH_MU / leo.semi_major_axis).sqrt()).abs() < 1e-12);
+        assert!(state.position.dot(&state.velocity).abs() < 1e-6);
+        let expected_energy = -EARTH_MU / (2.0 * leo.semi_major_axis);
+        assert!((specific_energy(&state) - expected_energy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn two_body_state_repeats_after_one_period() {
+        let orbit = elements(EARTH_EQUATORIAL_RADIUS + 1_000.0, 0.1);
+        let start = orbit.epoch as f64 + 500.0;
+        let initial = orbit.propagate(PropagationModel::TwoBody, start);
+        let energy = specific_energy(&initial);
+        let momentum = angular_momentum(&initial);
+
+        for step in 1..=10 {
+            let state = orbit.propagate(
+                PropagationModel::TwoBody,
+                start + orbit.period() * step as f64 / 10.0,
+            );
+            assert!((specific_energy(&state) - energy).abs() < 1e-9);
+            assert!(angular_momentum(&state).sub(&momentum).norm() < 1e-6);
+        }
+
+        let after = orbit.propagate(PropagationModel::TwoBody, start + orbit.period());
+        assert!(after.position.sub(&initial.position).norm() < 1e-6);
+        assert!(after.velocity.sub(&initial.velocity).norm() < 1e-9);
+    }
+
+    #[test]
+    fn geodetic_ecef_round_trip() {
+        for (latitude, longitude, altitude) in [
+            (0.0, 0.0, 0.0),
+            (51.4779, -0.0015, 0.046),
+            (-33.8688, 151.2093, 0.058),
+            (89.9, 45.0, 400.0),
+            (-45.0, -179.5, 35_786.0),
+            (90.0, 0.0, 400.0),
+            (-89.999_999, 120.0, 0.0),
+        ] {
+            let geodetic = Geodetic {
+                latitude,
+                longitude,
+                altitude,
+            };
+            let back = ecef_to_geodetic(&geodetic_to_ecef(&geodetic));
+            assert!((back.latitude - latitude).abs() < 1e-9);
+            assert!((back.longitude - longitude).abs() < 1e-9);
+            assert!((back.altitude - altitude).abs() < 1e-6);
+        }
+
+        // on the equator the ellipsoid sits at the equatorial radius, at the poles below it
+        let equator = geodetic_to_ecef(&Geodetic {
+            latitude: 0.0,
+            longitude: 90.0,
+            altitude: 0.0,
+        });
+        assert!((equator.y - EARTH_EQUATORIAL_RADIUS).abs() < 1e-9);
+        let pole = geodetic_to_ecef(&Geodetic {
+            latitude: 90.0,
+            longitude: 0.0,
+            altitude: 0.0,
+        });
+        assert!((pole.z - 6_356.752).abs() < 1e-3);
+
+        // exactly over a pole there is no horizontal distance to divide by
+        let above_pole = ecef_to_geodetic(&Vector3::new(0.0, 0.0, -(pole.z + 400.0)));
+        assert!((above_pole.latitude + 90.0).abs() < 1e-9);
+        assert!((above_pole.altitude - 400.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn derived_parameters_of_a_circular_orbit() {
+        let satellite = SatelliteApiResponse::from(test_satellite(
+            Pubkey::new_unique(),
+            25544,
+            "ISS",
+            OperationStatus::Active,
+        ));
+        let expected_period = 2.0 * PI * (satellite.semi_major_axis.powi(3) / EARTH_MU).sqrt();
+        let expected_energy = -EARTH_MU / (2.0 * satellite.semi_major_axis);
+
+        let derived = DerivedOrbitalParameters::from_satellite(&satellite);
+        assert!((derived.period_secs.unwrap() - expected_period).abs() < 1e-9);
+        assert!((derived.specific_orbital_energy.unwrap() - expected_energy).abs() < 1e-9);
+        assert_eq!(derived.orbit_class, Some(OrbitClass::Leo));
+        assert!(derived.warnings.is_empty());
+    }
+}
@@ -0,0 +1,475 @@
+use std::sync::Arc;
+
+use axum::{debug_handler, extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    ecef_to_geodetic, eci_to_ecef, fetch_elements, from_unix_seconds, geodetic_to_ecef,
+    parse_rfc3339, unix_seconds, ApiError, AppState, Geodetic, KeplerianElements, PropagationModel,
+    SatellitePath, ValidQuery, Vector3,
+};
+
+// bounds on how much work a single request can ask for
+pub const MAX_PREDICTION_WINDOW_DAYS: i64 = 10;
+pub const MAX_GROUND_TRACK_POINTS: usize = 10_000;
+pub const DEFAULT_GROUND_TRACK_STEP_SECS: f64 = 60.0;
+
+// coarse scan step for finding passes; short enough not to miss a low LEO pass
+const PASS_SCAN_STEP_SECS: f64 = 20.0;
+// rise and set times are refined to this precision
+const PASS_TIME_TOLERANCE_SECS: f64 = 0.1;
+
+// Observer on the WGS84 ellipsoid, degrees and km like the rest of the orbit module
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct GroundStation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct LookAngles {
+    // degrees clockwise from true north
+    pub azimuth: f64,
+    // degrees above the local horizon
+    pub elevation: f64,
+    // km
+    pub range: f64,
+}
+
+impl GroundStation {
+    pub fn ecef(&self) -> Vector3 {
+        geodetic_to_ecef(&Geodetic {
+            latitude: self.latitude,
+            longitude: self.longitude,
+            altitude: self.altitude,
+        })
+    }
+
+    // Azimuth, elevation and range to a satellite given in Earth-fixed coordinates
+    pub fn look_angles(&self, satellite_ecef: &Vector3) -> LookAngles {
+        let relative = satellite_ecef.sub(&self.ecef());
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.to_radians().sin_cos();
+
+        // east, north, up components of the line of sight
+        let east = -sin_lon * relative.x + cos_lon * relative.y;
+        let north =
+            -sin_lat * cos_lon * relative.x - sin_lat * sin_lon * relative.y + cos_lat * relative.z;
+        let up =
+            cos_lat * cos_lon * relative.x + cos_lat * sin_lon * relative.y + sin_lat * relative.z;
+
+        let range = relative.norm();
+        LookAngles {
+            azimuth: east.atan2(north).to_degrees().rem_euclid(360.0),
+            elevation: (up / range).asin().to_degrees(),
+            range,
+        }
+    }
+
+    fn validate(&self) -> Result<(), ApiError> {
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(ApiError::InvalidQuery {
+                reason: format!("lat {} must be between -90 and 90", self.latitude),
+            });
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(ApiError::InvalidQuery {
+                reason: format!("lon {} must be between -180 and 180", self.longitude),
+            });
+        }
+        if !self.altitude.is_finite() {
+            return Err(ApiError::InvalidQuery {
+                reason: "alt must be a finite number".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct PassEvent {
+    pub time: DateTime<Utc>,
+    pub azimuth: f64,
+    pub elevation: f64,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct Pass {
+    // None when the satellite is already above the minimum elevation at the window start
+    pub rise: Option<PassEvent>,
+    pub culmination: PassEvent,
+    // None when the satellite is still above the minimum elevation at the window end
+    pub set: Option<PassEvent>,
+    pub max_elevation: f64,
+    pub duration_secs: f64,
+}
+
+struct PassPredictor<'a> {
+    elements: &'a KeplerianElements,
+    model: PropagationModel,
+    station: &'a GroundStation,
+    min_elevation: f64,
+}
+
+impl PassPredictor<'_> {
+    fn look_angles(&self, unix_time: f64) -> LookAngles {
+        let state = self.elements.propagate(self.model, unix_time);
+        self.station
+            .look_angles(&eci_to_ecef(&state.position, unix_time))
+    }
+
+    fn visible(&self, unix_time: f64) -> bool {
+        self.look_angles(unix_time).elevation >= self.min_elevation
+    }
+
+    fn event(&self, unix_time: f64) -> PassEvent {
+        let angles = self.look_angles(unix_time);
+        PassEvent {
+            time: from_unix_seconds(unix_time),
+            azimuth: angles.azimuth,
+            elevation: angles.elevation,
+        }
+    }
+
+    // bisect a visibility change known to lie between `before` and `after`
+    fn crossing(&self, mut before: f64, mut after: f64) -> f64 {
+        let visible_before = self.visible(before);
+        while after - before > PASS_TIME_TOLERANCE_SECS {
+            let middle = (before + after) / 2.0;
+            if self.visible(middle) == visible_before {
+                before = middle;
+            } else {
+                after = middle;
+            }
+        }
+        (before + after) / 2.0
+    }
+
+    // golden-section search for the highest elevation within [start, end]
+    fn culmination(&self, mut start: f64, mut end: f64) -> f64 {
+        let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+        while end - start > PASS_TIME_TOLERANCE_SECS {
+            let lower = end - ratio * (end - start);
+            let upper = start + ratio * (end - start);
+            if self.look_angles(lower).elevation < self.look_angles(upper).elevation {
+                start = lower;
+            } else {
+                end = upper;
+            }
+        }
+        (start + end) / 2.0
+    }
+
+    fn pass(&self, rise: Option<f64>, set: Option<f64>, peak: f64, from: f64, to: f64) -> Pass {
+        let culmination = self.culmination(
+            (peak - PASS_SCAN_STEP_SECS).max(rise.unwrap_or(from)),
+            (peak + PASS_SCAN_STEP_SECS).min(set.unwrap_or(to)),
+        );
+        let culmination = self.event(culmination);
+        Pass {
+            rise: rise.map(|time| self.event(time)),
+            culmination,
+            set: set.map(|time| self.event(time)),
+            max_elevation: culmination.elevation,
+            duration_secs: set.unwrap_or(to) - rise.unwrap_or(from),
+        }
+    }
+
+    fn passes(&self, from: f64, to: f64) -> Vec<Pass> {
+        let mut passes = Vec::new();
+        // (rise, time of the highest sample, its elevation) of the pass in progress
+        let mut current: Option<(Option<f64>, f64, f64)> = None;
+
+        let mut previous = from;
+        let angles = self.look_angles(from);
+        if angles.elevation >= self.min_elevation {
+            current = Some((None, from, angles.elevation));
+        }
+        while previous < to {
+            let time = (previous + PASS_SCAN_STEP_SECS).min(to);
+            let elevation = self.look_angles(time).elevation;
+            let visible = elevation >= self.min_elevation;
+            match (current, visible) {
+                (None, true) => {
+                    let rise = self.crossing(previous, time);
+                    current = Some((Some(rise), time, elevation));
+                }
+                (Some((rise, _, peak_elevation)), true) => {
+                    if elevation > peak_elevation {
+                        current = Some((rise, time, elevation));
+                    }
+                }
+                (Some((rise, peak, _)), false) => {
+                    let set = self.crossing(previous, time);
+                    passes.push(self.pass(rise, Some(set), peak, from, to));
+                    current = None;
+                }
+                (None, false) => {}
+            }
+            previous = time;
+        }
+        if let Some((rise, peak, _)) = current {
+            passes.push(self.pass(rise, None, peak, from, to));
+        }
+        passes
+    }
+}
+
+// Shared time window for the prediction endpoints, defaulting to now and `default_span`
+pub(crate) fn parse_window(
+    from: Option<&str>,
+    to: Option<&str>,
+    default_span: Duration,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), ApiError> {
+    let from = match from {
+        Some(from) => parse_rfc3339("from", from)?,
+        None => Utc::now(),
+    };
+    let to = match to {
+        Some(to) => parse_rfc3339("to", to)?,
+        None => from + default_span,
+    };
+    if to <= from {
+        return Err(ApiError::InvalidQuery {
+            reason: format!("to {} must be after from {}", to, from),
+        });
+    }
+    if to - from > Duration::days(MAX_PREDICTION_WINDOW_DAYS) {
+        return Err(ApiError::InvalidQuery {
+            reason: format!(
+                "the window from {} to {} is longer than {} days",
+                from, to, MAX_PREDICTION_WINDOW_DAYS
+            ),
+        });
+    }
+    Ok((from, to))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PassesQuery {
+    pub lat: f64,
+    pub lon: f64,
+    // km above the ellipsoid
+    pub alt: Option<f64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub min_elevation: Option<f64>,
+    pub model: Option<PropagationModel>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SatellitePassesApiResponse {
+    pub norad_id: u64,
+    pub station: GroundStation,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub min_elevation: f64,
+    pub model: PropagationModel,
+    pub passes: Vec<Pass>,
+}
+
+#[debug_handler]
+pub async fn get_satellite_passes(
+    SatellitePath(seeds): SatellitePath,
+    ValidQuery(query): ValidQuery<PassesQuery>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<SatellitePassesApiResponse>, ApiError> {
+    let station = GroundStation {
+        latitude: query.lat,
+        longitude: query.lon,
+        altitude: query.alt.unwrap_or(0.0),
+    };
+    station.validate()?;
+    let min_elevation = query.min_elevation.unwrap_or(0.0);
+    if !(-90.0..=90.0).contains(&min_elevation) {
+        return Err(ApiError::InvalidQuery {
+            reason: format!("min_elevation {} must be between -90 and 90", min_elevation),
+        });
+    }
+    let (from, to) = parse_window(
+        query.from.as_deref(),
+        query.to.as_deref(),
+        Duration::days(1),
+    )?;
+    let model = query.model.unwrap_or(PropagationModel::TwoBody);
+
+    let (satellite, elements) = fetch_elements(&app_state, &seeds).await?;
+
+    println!(
+        "Predicting passes of NORAD ID {} over ({}, {}) from {} to {}",
+        satellite.norad_id, station.latitude, station.longitude, from, to
+    );
+    // stepping through the window is CPU bound, keep it off the async workers
+    let (start, end) = (unix_seconds(&from), unix_seconds(&to));
+    let passes = tokio::task::spawn_blocking(move || {
+        let predictor = PassPredictor {
+            elements: &elements,
+            model,
+            station: &station,
+            min_elevation,
+        };
+        predictor.passes(start, end)
+    })
+    .await
+    .map_err(|e| ApiError::Internal {
+        reason: format!("pass prediction failed: {}", e),
+    })?;
+
+    Ok(Json(SatellitePassesApiResponse {
+        norad_id: satellite.norad_id,
+        station,
+        from,
+        to,
+        min_elevation,
+        model,
+        passes,
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct GroundTrackQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // seconds between points
+    pub step: Option<f64>,
+    pub model: Option<PropagationModel>,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct GroundTrackPoint {
+    pub time: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct GroundTrackApiResponse {
+    pub norad_id: u64,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub step: f64,
+    pub model: PropagationModel,
+    pub points: Vec<GroundTrackPoint>,
+}
+
+#[debug_handler]
+pub async fn get_satellite_ground_track(
+    SatellitePath(seeds): SatellitePath,
+    ValidQuery(query): ValidQuery<GroundTrackQuery>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<GroundTrackApiResponse>, ApiError> {
+    let step = query.step.unwrap_or(DEFAULT_GROUND_TRACK_STEP_SECS);
+    if !step.is_finite() || step < 1.0 {
+        return Err(ApiError::InvalidQuery {
+            reason: format!("step {} must be at least 1 second", step),
+        });
+    }
+    let model = query.model.unwrap_or(PropagationModel::TwoBody);
+
+    let (satellite, elements) = fetch_elements(&app_state, &seeds).await?;
+
+    // one orbit unless asked otherwise
+    let period = Duration::milliseconds((elements.period() * 1_000.0) as i64);
+    let (from, to) = parse_window(query.from.as_deref(), query.to.as_deref(), period)?;
+    let (start, end) = (unix_seconds(&from), unix_seconds(&to));
+    let count = ((end - start) / step).floor() as usize + 1;
+    if count > MAX_GROUND_TRACK_POINTS {
+        return Err(ApiError::InvalidQuery {
+            reason: format!(
+                "{} points requested, at most {} are returned; raise step or shorten the window",
+                count, MAX_GROUND_TRACK_POINTS
+            ),
+        });
+    }
+
+    println!(
+        "Computing ground track of NORAD ID {} from {} to {} every {}s",
+        satellite.norad_id, from, to, step
+    );
+    // up to MAX_GROUND_TRACK_POINTS propagations, so off the async workers like passes
+    let points = tokio::task::spawn_blocking(move || {
+        (0..count)
+            .map(|index| {
+                let unix_time = start + index as f64 * step;
+                let state = elements.propagate(model, unix_time);
+                let geodetic = ecef_to_geodetic(&eci_to_ecef(&state.position, unix_time));
+                GroundTrackPoint {
+                    time: from_unix_seconds(unix_time),
+                    latitude: geodetic.latitude,
+                    longitude: geodetic.longitude,
+                    altitude: geodetic.altitude,
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| ApiError::Internal {
+        reason: format!("ground track computation failed: {}", e),
+    })?;
+
+    Ok(Json(GroundTrackApiResponse {
+        norad_id: satellite.norad_id,
+        from,
+        to,
+        step,
+        model,
+        points,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EARTH_EQUATORIAL_RADIUS;
+
+    #[test]
+    fn station_under_the_ground_track_sees_an_overhead_pass() {
+        let elements = KeplerianElements {
+            semi_major_axis: EARTH_EQUATORIAL_RADIUS + 400.0,
+            eccentricity: 0.0,
+            inclination: 51.6,
+            raan: 30.0,
+            arg_of_periapsis: 0.0,
+            mean_anomaly: 0.0,
+            epoch: 1_700_000_000,
+        };
+        let overhead = elements.epoch as f64 + 1_000.0;
+        let state = elements.propagate(PropagationModel::TwoBody, overhead);
+        let sub_satellite_point = ecef_to_geodetic(&eci_to_ecef(&state.position, overhead));
+        let station = GroundStation {
+            latitude: sub_satellite_point.latitude,
+            longitude: sub_satellite_point.longitude,
+            altitude: 0.0,
+        };
+        let predictor = PassPredictor {
+            elements: &elements,
+            model: PropagationModel::TwoBody,
+            station: &station,
+            min_elevation: 10.0,
+        };
+
+        // a LEO pass lasts minutes, and the next one is at least an orbit away
+        let passes = predictor.passes(overhead - 900.0, overhead + 900.0);
+        assert_eq!(passes.len(), 1);
+        let pass = passes[0];
+        assert!(pass.max_elevation > 89.5, "{}", pass.max_elevation);
+        let culmination = unix_seconds(&pass.culmination.time);
+        assert!((culmination - overhead).abs() < 1.0);
+
+        let rise = unix_seconds(&pass.rise.unwrap().time);
+        let set = unix_seconds(&pass.set.unwrap().time);
+        assert!(rise < culmination && culmination < set);
+        assert!((pass.rise.unwrap().elevation - 10.0).abs() < 0.5);
+        // almost symmetric; only the Earth turning underneath skews it
+        assert!(((culmination - rise) - (set - culmination)).abs() < 5.0);
+        assert!((pass.duration_secs - (set - rise)).abs() < 1e-6);
+
+        // half an orbit later the satellite is on the far side of the Earth
+        let far_side = overhead + elements.period() / 2.0;
+        assert!(predictor
+            .passes(far_side - 300.0, far_side + 300.0)
+            .is_empty());
+    }
+}
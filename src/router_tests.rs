@@ -0,0 +1,711 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Request, StatusCode},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+use borsh::BorshDeserialize;
+use chrono::DateTime;
+use serde_json::{json, Value};
+use solana_sdk::{
+    account::Account, message::Message, pubkey::Pubkey, signature::Signature,
+    transaction::Transaction,
+};
+use tower::ServiceExt;
+
+use crate::{
+    satellite::tests::{satellite_account, test_satellite, LAUNCH_DATE},
+    *,
+};
+
+const ISS_NORAD_ID: u64 = 25544;
+
+pub(crate) fn test_app_state(account_source: Arc<dyn AccountSource>) -> Arc<AppState> {
+    let config = AppConfig::from_raw(RawConfig {
+        account_source: Some("memory".to_string()),
+        account_dir: Some("accounts".to_string()),
+        ..RawConfig::default()
+    })
+    .unwrap();
+    Arc::new(AppState {
+        program_id: config.program_id,
+        account_source,
+        satellite_cache: SatelliteCache::new(config.cache_ttl, SATELLITE_CACHE_MAX_ENTRIES),
+        satellite_streams: SatelliteStreams::new(STREAM_CHANNEL_CAPACITY),
+        satellite_events: SatelliteEvents::new(EVENT_CHANNEL_CAPACITY),
+        fruits: Arc::new(InMemoryFruitRepository::with_default_fruits()),
+        config,
+    })
+}
+
+// The router over an in-memory account source that tests seed directly
+struct TestApp {
+    source: Arc<InMemoryAccountSource>,
+    program_id: Pubkey,
+    router: Router,
+}
+
+impl TestApp {
+    fn new() -> Self {
+        let source = Arc::new(InMemoryAccountSource::new());
+        let app_state = test_app_state(source.clone());
+        TestApp {
+            source,
+            program_id: app_state.program_id,
+            router: router(app_state),
+        }
+    }
+
+    fn seeds(norad_id: u64) -> SatelliteSeeds {
+        SatelliteSeeds {
+            user_authority: Pubkey::new_unique(),
+            registry_authority: Pubkey::new_unique(),
+            norad_id,
+        }
+    }
+
+    fn pda(&self, seeds: &SatelliteSeeds) -> Pubkey {
+        seeds.pda(&self.program_id).0
+    }
+
+    // Store a satellite at the PDA of its seeds, owned by the seeds' user authority
+    fn seed(&self, seeds: &SatelliteSeeds, name: &str, status: OperationStatus) -> Pubkey {
+        let satellite = test_satellite(seeds.user_authority, seeds.norad_id, name, status);
+        let address = self.pda(seeds);
+        self.source
+            .insert(address, satellite_account(&self.program_id, &satellite));
+        address
+    }
+
+    async fn send(&self, request: Request<Body>) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, headers, body.to_vec())
+    }
+
+    async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let (status, _, body) = self.send(request).await;
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let (status, _, body) = self.send(request).await;
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+}
+
+fn satellite_path(seeds: &SatelliteSeeds) -> String {
+    format!(
+        "/satellites/{}/{}/{}",
+        seeds.user_authority, seeds.registry_authority, seeds.norad_id
+    )
+}
+
+fn names(satellites: &Value) -> Vec<String> {
+    let mut names = satellites
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|satellite| satellite["name"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn list_applies_rpc_and_decoded_filters() {
+    let app = TestApp::new();
+    let iss = TestApp::seeds(ISS_NORAD_ID);
+    let tiangong = SatelliteSeeds {
+        norad_id: 48274,
+        ..iss
+    };
+    let hubble = TestApp::seeds(20580);
+    app.seed(&iss, "ISS", OperationStatus::Active);
+    app.seed(&tiangong, "Tiangong", OperationStatus::Maintenance);
+    app.seed(&hubble, "Hubble", OperationStatus::Offline);
+    // owned by the program but not a satellite, so it never matches the discriminator filter
+    app.source.insert(
+        Pubkey::new_unique(),
+        Account {
+            lamports: 1,
+            data: vec![0; 64],
+            owner: app.program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (status, all) = app.get("/satellites").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&all), ["Hubble", "ISS", "Tiangong"]);
+
+    let (_, owned) = app
+        .get(&format!("/satellites?owner={}", iss.user_authority))
+        .await;
+    assert_eq!(names(&owned), ["ISS", "Tiangong"]);
+
+    let (_, offline) = app.get("/satellites?operation_status=Offline").await;
+    assert_eq!(names(&offline), ["Hubble"]);
+
+    let (_, by_norad_id) = app.get("/satellites?norad_id=48274").await;
+    assert_eq!(names(&by_norad_id), ["Tiangong"]);
+
+    let (_, by_name) = app.get("/satellites?name=iss&country=us").await;
+    assert_eq!(names(&by_name), ["ISS"]);
+
+    let (status, error) = app.get("/satellites?owner=not-a-pubkey").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn lookup_maps_account_state_to_status() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+
+    let (status, satellite) = app.get(&satellite_path(&seeds)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(satellite["name"], "ISS");
+    assert_eq!(satellite["norad_id"], ISS_NORAD_ID);
+    assert_eq!(satellite["operation_status"], "Active");
+    assert!(satellite.get("derived").is_none());
+
+    let (_, with_derived) = app
+        .get(&format!("{}?include=derived", satellite_path(&seeds)))
+        .await;
+    assert!(with_derived["derived"]["period_secs"].as_f64().unwrap() > 0.0);
+
+    let missing = SatelliteSeeds {
+        norad_id: 1,
+        ..seeds
+    };
+    let (status, error) = app.get(&satellite_path(&missing)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["code"], "account_not_found");
+
+    // something else lives at the PDA
+    let foreign = SatelliteSeeds {
+        norad_id: 2,
+        ..seeds
+    };
+    let mut account = satellite_account(
+        &app.program_id,
+        &test_satellite(seeds.user_authority, 2, "Foreign", OperationStatus::Active),
+    );
+    account.owner = Pubkey::new_unique();
+    app.source.insert(app.pda(&foreign), account);
+    let (status, error) = app.get(&satellite_path(&foreign)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(error["code"], "account_owner_mismatch");
+
+    // the program owns it, but it is some other account type
+    let other_type = SatelliteSeeds {
+        norad_id: 3,
+        ..seeds
+    };
+    let mut account = satellite_account(
+        &app.program_id,
+        &test_satellite(seeds.user_authority, 3, "Other", OperationStatus::Active),
+    );
+    account.data[..SATELLITE_DISCRIMINATOR_LEN].fill(0);
+    app.source.insert(app.pda(&other_type), account);
+    let (status, error) = app.get(&satellite_path(&other_type)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["code"], "account_discriminator_mismatch");
+
+    let (status, error) = app
+        .get(&format!(
+            "/satellites/{}/{}/not-a-number",
+            seeds.user_authority, seeds.registry_authority
+        ))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_path_parameter");
+}
+
+#[tokio::test]
+async fn unknown_routes_and_methods_use_the_error_body() {
+    let app = TestApp::new();
+
+    let (status, error) = app.get("/nowhere").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["code"], "route_not_found");
+
+    let (status, error) = app.post("/satellites", json!({})).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(error["code"], "method_not_allowed");
+}
+
+// The single instruction of an unsigned transaction returned by a builder endpoint
+fn decode_instruction(response: &Value) -> (Message, Vec<u8>) {
+    let bytes = BASE64_STANDARD
+        .decode(response["transaction"].as_str().unwrap())
+        .unwrap();
+    let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+    let data = transaction.message.instructions[0].data.clone();
+    (transaction.message, data)
+}
+
+#[tokio::test]
+async fn create_transaction_encodes_the_instruction() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+
+    let mut fields = json!({
+        "user_authority": seeds.user_authority.to_string(),
+        "registry_authority": seeds.registry_authority.to_string(),
+        "name": "ISS",
+        "country": "US",
+        "norad_id": ISS_NORAD_ID,
+        "launch_date": LAUNCH_DATE,
+        "orbit_type": "LEO",
+        "inclination": 51.6,
+        "altitude": 400.0,
+        "semi_major_axis": 6778.137,
+        "eccentricity": 0.0,
+        "raan": 0.0,
+        "arg_of_periapsis": 0.0,
+        "maneuver_type": "StationKeeping",
+        "operation_status": "Active",
+    });
+    let (status, created) = app
+        .post("/satellites/transactions/create", fields.clone())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["instruction"], "create_satellite");
+    assert_eq!(created["satellite"], app.pda(&seeds).to_string());
+    assert_eq!(created["fee_payer"], seeds.user_authority.to_string());
+    let (message, data) = decode_instruction(&created);
+    assert_eq!(message.account_keys[0], seeds.user_authority);
+    assert_eq!(message.header.num_required_signatures, 1);
+    assert_eq!(data[..8], instruction_discriminator("create_satellite"));
+    let args = CreateSatelliteArgs::try_from_slice(&data[8..]).unwrap();
+    assert_eq!(args.name, "ISS");
+    assert_eq!(args.norad_id, ISS_NORAD_ID);
+
+    fields["name"] = json!("x".repeat(SATELLITE_STRING_MAX_LEN + 1));
+    let (status, error) = app.post("/satellites/transactions/create", fields).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["details"]["field"], "name");
+}
+
+#[tokio::test]
+async fn update_transactions_encode_the_instruction() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    let path = satellite_path(&seeds);
+
+    let (status, updated) = app
+        .post(
+            &format!("{}/status", path),
+            json!({ "operation_status": "Offline" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["instruction"], "update_status");
+    assert_eq!(updated["satellite"], app.pda(&seeds).to_string());
+    let (_, data) = decode_instruction(&updated);
+    assert_eq!(data[..8], instruction_discriminator("update_status"));
+    assert_eq!(data[8..], [OperationStatus::Offline as u8]);
+
+    let (status, maneuver) = app
+        .post(
+            &format!("{}/maneuvers", path),
+            json!({ "maneuver_type": "OrbitRaising" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, data) = decode_instruction(&maneuver);
+    assert_eq!(data[..8], instruction_discriminator("record_maneuver"));
+    assert_eq!(data[8..], [ManeuverType::OrbitRaising as u8]);
+
+    let (status, error) = app
+        .post(
+            &format!("{}/status", path),
+            json!({ "operation_status": "Exploded" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["code"], "validation_failed");
+}
+
+#[tokio::test]
+async fn position_is_on_the_orbit() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+    let semi_major_axis = EARTH_EQUATORIAL_RADIUS + 400.0;
+
+    for at in ["2023-11-14T22:13:20Z", "2023-11-15T03:00:00Z"] {
+        let (status, position) = app
+            .get(&format!("{}/position?at={}", satellite_path(&seeds), at))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(position["at"], at);
+        let eci = &position["position_eci"];
+        let radius = Vector3::new(
+            eci["x"].as_f64().unwrap(),
+            eci["y"].as_f64().unwrap(),
+            eci["z"].as_f64().unwrap(),
+        )
+        .norm();
+        assert!((radius - semi_major_axis).abs() < 1e-6);
+        let speed = position["speed"].as_f64().unwrap();
+        assert!((speed - (EARTH_MU / semi_major_axis).sqrt()).abs() < 1e-9);
+        assert!(position["geodetic"]["latitude"].as_f64().unwrap().abs() <= 51.6 + 0.2);
+    }
+
+    let (status, error) = app
+        .get(&format!("{}/position?at=yesterday", satellite_path(&seeds)))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn passes_stay_within_the_window() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+
+    let (status, response) = app
+        .get(&format!(
+            "{}/passes?lat=40&lon=-75&from=2023-11-15T00:00:00Z&to=2023-11-16T00:00:00Z&min_elevation=10",
+            satellite_path(&seeds)
+        ))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let from = DateTime::parse_from_rfc3339("2023-11-15T00:00:00Z").unwrap();
+    let to = DateTime::parse_from_rfc3339("2023-11-16T00:00:00Z").unwrap();
+    let passes = response["passes"].as_array().unwrap();
+    // a 51.6 degree orbit crosses 40 degrees latitude several times a day
+    assert!(!passes.is_empty());
+    for pass in passes {
+        let max_elevation = pass["max_elevation"].as_f64().unwrap();
+        assert!((10.0..=90.0).contains(&max_elevation));
+        let culmination =
+            DateTime::parse_from_rfc3339(pass["culmination"]["time"].as_str().unwrap()).unwrap();
+        assert!(from <= culmination && culmination <= to);
+    }
+
+    let (status, error) = app
+        .get(&format!("{}/passes?lat=120&lon=0", satellite_path(&seeds)))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn lookup_renders_tle_and_omm() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+
+    let request = Request::get(format!("{}.tle", satellite_path(&seeds)))
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, body) = app.send(request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+    let text = String::from_utf8(body).unwrap();
+    let parsed = parse_tle(&text).unwrap();
+    assert_eq!(parsed.name.as_deref(), Some("ISS"));
+    assert_eq!(parsed.norad_id, ISS_NORAD_ID);
+    assert!((parsed.inclination - 51.6).abs() < 1e-4);
+
+    let (status, omm) = app
+        .get(&format!("{}.omm.json", satellite_path(&seeds)))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(omm["OBJECT_NAME"], "ISS");
+    assert_eq!(omm["NORAD_CAT_ID"], ISS_NORAD_ID);
+    assert_eq!(omm["EPOCH"], "2023-11-14T22:13:20.000000");
+    assert_eq!(omm["REF_FRAME"], "EME2000");
+    assert_eq!(omm["MEAN_ELEMENT_THEORY"], "OSCULATING");
+
+    // and the rendered text imports back into registry fields
+    let (status, fields) = app
+        .post("/satellites/tle", json!({ "tle": text, "country": "US" }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fields["name"], "ISS");
+    assert_eq!(fields["norad_id"], ISS_NORAD_ID);
+    assert_eq!(fields["orbit_type"], "LEO");
+    assert!((fields["semi_major_axis"].as_f64().unwrap() - 6_778.137).abs() < 1e-3);
+
+    let (status, error) = app
+        .post(
+            "/satellites/tle",
+            json!({ "tle": "garbage", "country": "US" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["code"], "validation_failed");
+}
+
+#[tokio::test]
+async fn conjunctions_screen_the_registry() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+    // every test satellite flies the same orbit, so this one never leaves the primary's side
+    let twin = TestApp::seeds(48274);
+    app.seed(&twin, "Twin", OperationStatus::Active);
+
+    let (status, response) = app
+        .get(&format!(
+            "{}/conjunctions?from=2023-11-15T00:00:00Z&to=2023-11-15T01:00:00Z",
+            satellite_path(&seeds)
+        ))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["primary"]["norad_id"], ISS_NORAD_ID);
+    assert_eq!(response["screened"], 1);
+    let conjunctions = response["conjunctions"].as_array().unwrap();
+    assert!(!conjunctions.is_empty());
+    for conjunction in conjunctions {
+        assert_eq!(conjunction["secondary"]["name"], "Twin");
+        assert!(conjunction["miss_distance"].as_f64().unwrap() < 1e-6);
+    }
+
+    let (status, error) = app
+        .get(&format!(
+            "{}/conjunctions?with=not-a-satellite",
+            satellite_path(&seeds)
+        ))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn batch_reports_each_item() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    let address = app.seed(&seeds, "ISS", OperationStatus::Active);
+
+    let (status, response) = app
+        .post(
+            "/satellites/batch",
+            json!({ "satellites": [
+                {
+                    "user_authority": seeds.user_authority.to_string(),
+                    "registry_authority": seeds.registry_authority.to_string(),
+                    "norad_id": ISS_NORAD_ID,
+                },
+                {
+                    "user_authority": seeds.user_authority.to_string(),
+                    "registry_authority": seeds.registry_authority.to_string(),
+                    "norad_id": 1,
+                },
+                {
+                    "user_authority": "not-a-pubkey",
+                    "registry_authority": seeds.registry_authority.to_string(),
+                    "norad_id": ISS_NORAD_ID,
+                },
+            ]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["status"], 200);
+    assert_eq!(results[0]["address"], address.to_string());
+    assert_eq!(results[0]["satellite"]["name"], "ISS");
+    assert_eq!(results[1]["status"], 404);
+    assert_eq!(results[1]["error"]["code"], "account_not_found");
+    assert_eq!(results[2]["status"], 422);
+    assert!(results[2]["address"].is_null());
+
+    let (status, error) = app
+        .post("/satellites/batch", json!({ "satellites": [] }))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["code"], "validation_failed");
+}
+
+#[tokio::test]
+async fn pda_matches_derivation() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    let path = format!(
+        "/satellites/pda/{}/{}/{}",
+        seeds.user_authority, seeds.registry_authority, seeds.norad_id
+    );
+
+    let (status, pda) = app.get(&path).await;
+    assert_eq!(status, StatusCode::OK);
+    let (address, bump) = seeds.pda(&app.program_id);
+    assert_eq!(pda["address"], address.to_string());
+    assert_eq!(pda["bump"], bump);
+    assert_eq!(pda["program_id"], app.program_id.to_string());
+
+    let other_program = Pubkey::new_unique();
+    let (_, pda) = app
+        .get(&format!("{}?program_id={}", path, other_program))
+        .await;
+    assert_eq!(pda["address"], seeds.pda(&other_program).0.to_string());
+}
+
+#[tokio::test]
+async fn lookup_honours_if_none_match() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+
+    let request = Request::get(satellite_path(&seeds))
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, _) = app.send(request).await;
+    assert_eq!(status, StatusCode::OK);
+    let etag = headers[header::ETAG].clone();
+
+    let request = Request::get(satellite_path(&seeds))
+        .header(header::IF_NONE_MATCH, etag.clone())
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, body) = app.send(request).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(headers[header::ETAG], etag);
+    assert!(body.is_empty());
+
+    // each representation has its own tag
+    let request = Request::get(format!("{}.tle", satellite_path(&seeds)))
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())
+        .unwrap();
+    let (status, _, _) = app.send(request).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn stream_requires_a_websocket_upgrade() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+
+    let (status, error) = app.get(&format!("{}/stream", satellite_path(&seeds))).await;
+    assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+    assert_eq!(error["code"], "upgrade_required");
+    assert_eq!(error["details"]["protocol"], "websocket");
+}
+
+// A confirmed transaction running one instruction, as getTransaction would return it
+fn historical_transaction(
+    instruction: solana_sdk::instruction::Instruction,
+    fee_payer: &Pubkey,
+    slot: u64,
+    failed: bool,
+) -> HistoricalTransaction {
+    let message = Message::new(&[instruction], Some(fee_payer));
+    HistoricalTransaction {
+        signature: Signature::new_unique(),
+        slot,
+        block_time: Some(LAUNCH_DATE + slot as i64),
+        failed,
+        account_keys: message.account_keys,
+        instructions: message.instructions,
+    }
+}
+
+#[tokio::test]
+async fn history_pages_through_program_instructions() {
+    let app = TestApp::new();
+    let seeds = TestApp::seeds(ISS_NORAD_ID);
+    app.seed(&seeds, "ISS", OperationStatus::Active);
+    let fields = |operation_status| CreateSatelliteArgs {
+        name: "ISS".to_string(),
+        country: "US".to_string(),
+        norad_id: ISS_NORAD_ID,
+        launch_date: LAUNCH_DATE,
+        orbit_type: "LEO".to_string(),
+        inclination: 51.6,
+        altitude: 400.0,
+        semi_major_axis: 6778.137,
+        eccentricity: 0.0,
+        raan: 0.0,
+        arg_of_periapsis: 0.0,
+        maneuver_type: ManeuverType::StationKeeping,
+        operation_status,
+    };
+    let (create, _) =
+        create_satellite_instruction(&app.program_id, &seeds, &fields(OperationStatus::Active))
+            .unwrap();
+    let created = historical_transaction(create, &seeds.user_authority, 10, false);
+    let (set_offline, _) = update_satellite_instruction(
+        &app.program_id,
+        &seeds,
+        "update_status",
+        &OperationStatus::Offline,
+    )
+    .unwrap();
+    let failed = historical_transaction(set_offline.clone(), &seeds.user_authority, 11, true);
+    let updated = historical_transaction(set_offline, &seeds.user_authority, 12, false);
+    let (raise, _) = update_satellite_instruction(
+        &app.program_id,
+        &seeds,
+        "record_maneuver",
+        &ManeuverType::OrbitRaising,
+    )
+    .unwrap();
+    let raised = historical_transaction(raise, &seeds.user_authority, 13, false);
+    // another satellite's history
+    let other = SatelliteSeeds {
+        norad_id: 48274,
+        ..seeds
+    };
+    let (unrelated, _) =
+        create_satellite_instruction(&app.program_id, &other, &fields(OperationStatus::Active))
+            .unwrap();
+    for transaction in [
+        created.clone(),
+        failed,
+        updated.clone(),
+        historical_transaction(unrelated, &seeds.user_authority, 14, false),
+        raised.clone(),
+    ] {
+        app.source.insert_transaction(transaction);
+    }
+
+    let path = format!("{}/history", satellite_path(&seeds));
+    let (status, page) = app.get(&format!("{}?limit=2", path)).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = page["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["instruction"], "record_maneuver");
+    assert_eq!(entries[0]["maneuver_type"], "OrbitRaising");
+    assert_eq!(entries[0]["signature"], raised.signature.to_string());
+    assert_eq!(entries[1]["instruction"], "update_status");
+    assert_eq!(entries[1]["operation_status"], "Offline");
+    assert_eq!(entries[1]["signer"], seeds.user_authority.to_string());
+    assert_eq!(page["next_cursor"], updated.signature.to_string());
+
+    // the failed update is skipped and the walk ends with the create
+    let (status, page) = app
+        .get(&format!(
+            "{}?limit=2&before={}",
+            path,
+            page["next_cursor"].as_str().unwrap()
+        ))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = page["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["instruction"], "create_satellite");
+    assert_eq!(entries[0]["signature"], created.signature.to_string());
+    assert_eq!(entries[0]["operation_status"], "Active");
+    assert!(page["next_cursor"].is_null());
+
+    let (status, _) = app.get(&format!("{}?limit=0", path)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,77 @@
+use std::{fmt, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    Request, StatusCode,
+};
+use reqwest_middleware::{ClientBuilder, Middleware, Next};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client::{http_sender::HttpSender, rpc_client::RpcClientConfig};
+use solana_sdk::commitment_config::CommitmentConfig;
+use task_local_extensions::Extensions;
+
+// The rpc node answered 429. Carries its Retry-After (in seconds) so our clients get the same
+// advice instead of a guess.
+#[derive(Debug)]
+pub struct RpcRateLimited {
+    pub retry_after: Option<u64>,
+}
+
+impl fmt::Display for RpcRateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.retry_after {
+            Some(secs) => write!(f, "rate limited by the rpc node, retry after {}s", secs),
+            None => write!(f, "rate limited by the rpc node"),
+        }
+    }
+}
+
+impl std::error::Error for RpcRateLimited {}
+
+// The stock http sender sleeps through up to five 429s before giving up and then drops the
+// response headers; fail on the first one instead and keep its Retry-After
+struct RateLimitPassThrough;
+
+#[async_trait]
+impl Middleware for RateLimitPassThrough {
+    async fn handle(
+        &self,
+        request: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let response = next.run(request, extensions).await?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+        let retry_after = retry_after_secs(response.headers());
+        Err(reqwest_middleware::Error::Middleware(
+            RpcRateLimited { retry_after }.into(),
+        ))
+    }
+}
+
+// Only the delay-seconds form; an HTTP date is rare enough from rpc providers to ignore
+fn retry_after_secs(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+pub fn rpc_client(rpc_url: String, timeout: Duration, commitment: CommitmentConfig) -> RpcClient {
+    let client = reqwest::Client::builder()
+        .default_headers(HttpSender::default_headers())
+        .timeout(timeout)
+        .pool_idle_timeout(timeout)
+        .build()
+        .expect("build rpc http client");
+    let client = ClientBuilder::new(client)
+        .with(RateLimitPassThrough)
+        .build();
+    RpcClient::new_sender(
+        HttpSender::new_with_client_with_middleware(rpc_url, client),
+        RpcClientConfig::with_commitment(commitment),
+    )
+}
@@ -2,16 +2,186 @@ use std::{str::FromStr, sync::Arc};
 
 use axum::{
     debug_handler,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::rejection::PathRejection,
+    extract::{FromRequestParts, Path, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use borsh::BorshDeserialize;
-use solana_sdk::pubkey::Pubkey;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde_json::{json, Value};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, hash::hash, pubkey::Pubkey};
 
-use crate::AppState;
+use crate::{
+    caching_headers, if_none_match, render_omm, render_tle, ApiError, AppState, CachedSatellite,
+    DerivedOrbitalParameters, ValidQuery,
+};
+
+// Anchor prefixes every account with the first 8 bytes of sha256("account:<Name>")
+pub const SATELLITE_DISCRIMINATOR_LEN: usize = 8;
+
+// Byte offsets of the fixed-position fields in the account data (discriminator included),
+// used to push filters down to the rpc node as memcmp comparisons
+pub const SATELLITE_OWNER_OFFSET: usize = SATELLITE_DISCRIMINATOR_LEN;
+pub const SATELLITE_NORAD_ID_OFFSET: usize = SATELLITE_OWNER_OFFSET + 32 + 34 + 34;
+pub const SATELLITE_MANEUVER_TYPE_OFFSET: usize =
+    SATELLITE_NORAD_ID_OFFSET + 8 + 8 + 8 + 34 + 6 * 8;
+pub const SATELLITE_OPERATION_STATUS_OFFSET: usize = SATELLITE_MANEUVER_TYPE_OFFSET + 1;
+
+pub fn satellite_discriminator() -> [u8; SATELLITE_DISCRIMINATOR_LEN] {
+    let digest = hash(b"account:Satellite").to_bytes();
+    let mut discriminator = [0u8; SATELLITE_DISCRIMINATOR_LEN];
+    discriminator.copy_from_slice(&digest[..SATELLITE_DISCRIMINATOR_LEN]);
+    discriminator
+}
+
+// Strings are declared #[max_len(30)] in the program
+pub const SATELLITE_STRING_MAX_LEN: usize = 30;
+
+// The values the satellite PDA is derived from, as they appear in request paths
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SatelliteSeeds {
+    pub user_authority: Pubkey,
+    pub registry_authority: Pubkey,
+    pub norad_id: u64,
+}
 
-#[derive(Debug, BorshDeserialize)]
+impl SatelliteSeeds {
+    pub fn parse(
+        user_authority_str: &str,
+        registry_authority_str: &str,
+        norad_id_str: &str,
+    ) -> Result<Self, ApiError> {
+        let user_authority = Pubkey::from_str(user_authority_str).map_err(|e| {
+            eprintln!(
+                "Invalid user authority Pubkey: {}. Error: {}",
+                user_authority_str, e
+            );
+            ApiError::InvalidPathParameter {
+                name: "user_authority",
+                value: user_authority_str.to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+
+        let registry_authority = Pubkey::from_str(registry_authority_str).map_err(|e| {
+            eprintln!(
+                "Invalid registry authority Pubkey: {}. Error: {}",
+                registry_authority_str, e
+            );
+            ApiError::InvalidPathParameter {
+                name: "registry_authority",
+                value: registry_authority_str.to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+
+        let norad_id = norad_id_str.parse::<u64>().map_err(|e| {
+            eprintln!("Invalid NORAD ID: {}. Error: {}", norad_id_str, e);
+            ApiError::InvalidPathParameter {
+                name: "norad_id",
+                value: norad_id_str.to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+
+        Ok(SatelliteSeeds {
+            user_authority,
+            registry_authority,
+            norad_id,
+        })
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        derive_satellite_pda(
+            &self.user_authority,
+            &self.registry_authority,
+            self.norad_id,
+            program_id,
+        )
+    }
+}
+
+pub const SATELLITE_SEED_PREFIX: &[u8] = b"satellite";
+
+// The program derives every satellite account from
+// ["satellite", user_authority, registry_authority, norad_id as u64 little-endian]
+pub fn derive_satellite_pda(
+    user_authority: &Pubkey,
+    registry_authority: &Pubkey,
+    norad_id: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SATELLITE_SEED_PREFIX,
+            user_authority.as_ref(),
+            registry_authority.as_ref(),
+            &norad_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+// The {user_authority}/{registry_authority}/{norad_id} segments every single-satellite route
+// starts with
+async fn satellite_path_segments<S: Send + Sync>(
+    parts: &mut Parts,
+    state: &S,
+) -> Result<(String, String, String), ApiError> {
+    let Path(segments) = Path::<(String, String, String)>::from_request_parts(parts, state)
+        .await
+        .map_err(|e| match e {
+            // only a route registered without the three segments gets here
+            PathRejection::MissingPathParams(_) => ApiError::Internal {
+                reason: e.body_text(),
+            },
+            _ => ApiError::InvalidPathParameter {
+                name: "satellite",
+                value: parts.uri.path().to_string(),
+                reason: e.body_text(),
+            },
+        })?;
+    Ok(segments)
+}
+
+// Extracts the seeds of the satellite a route is about
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SatellitePath(pub SatelliteSeeds);
+
+impl<S: Send + Sync> FromRequestParts<S> for SatellitePath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let (user_authority, registry_authority, norad_id) =
+            satellite_path_segments(parts, state).await?;
+        SatelliteSeeds::parse(&user_authority, &registry_authority, &norad_id).map(SatellitePath)
+    }
+}
+
+// Like SatellitePath, for the lookup route whose NORAD ID may carry a format suffix
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SatelliteRepresentationPath {
+    pub seeds: SatelliteSeeds,
+    pub format: SatelliteFormat,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SatelliteRepresentationPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let (user_authority, registry_authority, norad_id) =
+            satellite_path_segments(parts, state).await?;
+        let (norad_id, format) = SatelliteFormat::split(&norad_id);
+        Ok(SatelliteRepresentationPath {
+            seeds: SatelliteSeeds::parse(&user_authority, &registry_authority, norad_id)?,
+            format,
+        })
+    }
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct Satellite {
     pub owner: Pubkey,
     // For #[max_len(30)] String, Borsh serializes as 4 bytes (length) + 30 bytes (data/padding) = 34 bytes
@@ -61,14 +231,40 @@ impl Satellite {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, BorshDeserialize)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
 pub enum OperationStatus {
     Active,
     Maintenance,
     Offline,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, BorshDeserialize)]
+impl OperationStatus {
+    pub const ALL: [OperationStatus; 3] = [
+        OperationStatus::Active,
+        OperationStatus::Maintenance,
+        OperationStatus::Offline,
+    ];
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
 pub enum ManeuverType {
     StationKeeping,
     OrbitRaising,
@@ -80,6 +276,19 @@ pub enum ManeuverType {
     Desaturation,
 }
 
+impl ManeuverType {
+    pub const ALL: [ManeuverType; 8] = [
+        ManeuverType::StationKeeping,
+        ManeuverType::OrbitRaising,
+        ManeuverType::OrbitLowering,
+        ManeuverType::InclinationChange,
+        ManeuverType::PhaseAdjustment,
+        ManeuverType::CollisionAvoidance,
+        ManeuverType::EndOfLife,
+        ManeuverType::Desaturation,
+    ];
+}
+
 #[derive(Debug, serde::Serialize, Clone)] // This one retains `serde::Serialize`
 pub struct SatelliteApiResponse {
     pub owner: Pubkey,
@@ -97,6 +306,18 @@ pub struct SatelliteApiResponse {
     pub arg_of_periapsis: f64,
     pub maneuver_type: ManeuverType,
     pub operation_status: OperationStatus,
+    // only computed when asked for with ?include=derived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derived: Option<DerivedOrbitalParameters>,
+}
+
+impl SatelliteApiResponse {
+    pub fn with_includes(mut self, includes: &SatelliteIncludes) -> Self {
+        if includes.derived {
+            self.derived = Some(DerivedOrbitalParameters::from_satellite(&self));
+        }
+        self
+    }
 }
 
 impl From<Satellite> for SatelliteApiResponse {
@@ -117,88 +338,782 @@ impl From<Satellite> for SatelliteApiResponse {
             arg_of_periapsis: account.arg_of_periapsis,
             maneuver_type: account.maneuver_type,
             operation_status: account.operation_status,
+            derived: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SatelliteAccountError {
+    OwnerMismatch {
+        address: Pubkey,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    DataTooShort {
+        address: Pubkey,
+        len: usize,
+    },
+    DiscriminatorMismatch {
+        address: Pubkey,
+        expected: [u8; SATELLITE_DISCRIMINATOR_LEN],
+        actual: [u8; SATELLITE_DISCRIMINATOR_LEN],
+    },
+    InvalidData {
+        address: Pubkey,
+        reason: String,
+    },
+}
+
+impl SatelliteAccountError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            // the address exists but belongs to another program
+            SatelliteAccountError::OwnerMismatch { .. } => StatusCode::CONFLICT,
+            SatelliteAccountError::DataTooShort { .. }
+            | SatelliteAccountError::DiscriminatorMismatch { .. }
+            | SatelliteAccountError::InvalidData { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            SatelliteAccountError::OwnerMismatch { .. } => "account_owner_mismatch",
+            SatelliteAccountError::DataTooShort { .. } => "account_data_too_short",
+            SatelliteAccountError::DiscriminatorMismatch { .. } => "account_discriminator_mismatch",
+            SatelliteAccountError::InvalidData { .. } => "account_data_invalid",
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        match self {
+            SatelliteAccountError::OwnerMismatch {
+                address,
+                expected,
+                actual,
+            } => json!({
+                "address": address.to_string(),
+                "expected_owner": expected.to_string(),
+                "actual_owner": actual.to_string(),
+            }),
+            SatelliteAccountError::DataTooShort { address, len } => json!({
+                "address": address.to_string(),
+                "data_len": len,
+            }),
+            SatelliteAccountError::DiscriminatorMismatch {
+                address,
+                expected,
+                actual,
+            } => json!({
+                "address": address.to_string(),
+                "expected_discriminator": expected,
+                "actual_discriminator": actual,
+            }),
+            SatelliteAccountError::InvalidData { address, reason } => json!({
+                "address": address.to_string(),
+                "reason": reason,
+            }),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            SatelliteAccountError::OwnerMismatch {
+                address,
+                expected,
+                actual,
+            } => format!(
+                "Account {} is owned by {}, expected the satellite program {}",
+                address, actual, expected
+            ),
+            SatelliteAccountError::DataTooShort { address, len } => format!(
+                "Account {} holds {} bytes, too short for an Anchor discriminator",
+                address, len
+            ),
+            SatelliteAccountError::DiscriminatorMismatch {
+                address,
+                expected,
+                actual,
+            } => format!(
+                "Account {} has discriminator {:?}, expected the Satellite discriminator {:?}",
+                address, actual, expected
+            ),
+            SatelliteAccountError::InvalidData { address, reason } => format!(
+                "Account {} could not be decoded as a Satellite: {}",
+                address, reason
+            ),
+        }
+    }
+}
+
+// Verify the owner and Anchor discriminator before handing the rest of the data to Borsh
+pub fn decode_satellite_account(
+    address: &Pubkey,
+    account: &Account,
+    program_id: &Pubkey,
+) -> Result<Satellite, SatelliteAccountError> {
+    if account.owner != *program_id {
+        return Err(SatelliteAccountError::OwnerMismatch {
+            address: *address,
+            expected: *program_id,
+            actual: account.owner,
+        });
+    }
+
+    if account.data.len() < SATELLITE_DISCRIMINATOR_LEN {
+        return Err(SatelliteAccountError::DataTooShort {
+            address: *address,
+            len: account.data.len(),
+        });
+    }
+
+    let (discriminator_bytes, mut data) = account.data.split_at(SATELLITE_DISCRIMINATOR_LEN);
+    let expected = satellite_discriminator();
+    if discriminator_bytes != expected {
+        let mut actual = [0u8; SATELLITE_DISCRIMINATOR_LEN];
+        actual.copy_from_slice(discriminator_bytes);
+        return Err(SatelliteAccountError::DiscriminatorMismatch {
+            address: *address,
+            expected,
+            actual,
+        });
+    }
+
+    // Anchor accounts may carry trailing space, so don't require every byte to be consumed
+    Satellite::deserialize(&mut data).map_err(|e| SatelliteAccountError::InvalidData {
+        address: *address,
+        reason: e.to_string(),
+    })
+}
+
+// Optional sections of a satellite response, requested as ?include=a,b
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SatelliteIncludes {
+    pub derived: bool,
+}
+
+impl SatelliteIncludes {
+    pub fn parse(include: Option<&str>) -> Result<Self, ApiError> {
+        let mut includes = SatelliteIncludes::default();
+        for section in include.unwrap_or_default().split(',').map(str::trim) {
+            match section {
+                "" => {}
+                "derived" => includes.derived = true,
+                other => {
+                    return Err(ApiError::InvalidQuery {
+                        reason: format!("unknown include '{}', expected one of: derived", other),
+                    })
+                }
+            }
         }
+        Ok(includes)
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SatelliteQuery {
+    pub include: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SatelliteListQuery {
+    pub owner: Option<String>,
+    pub norad_id: Option<u64>,
+    pub name: Option<String>,
+    pub country: Option<String>,
+    pub orbit_type: Option<String>,
+    pub operation_status: Option<OperationStatus>,
+    pub maneuver_type: Option<ManeuverType>,
+    pub include: Option<String>,
+}
+
+impl SatelliteListQuery {
+    // Filters that can be evaluated by the rpc node because the field sits at a fixed offset
+    pub fn rpc_filters(&self) -> Result<Vec<RpcFilterType>, ApiError> {
+        let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            satellite_discriminator().to_vec(),
+        ))];
+
+        if let Some(owner_str) = &self.owner {
+            let owner = Pubkey::from_str(owner_str).map_err(|e| {
+                eprintln!("Invalid owner Pubkey: {}. Error: {}", owner_str, e);
+                ApiError::InvalidQuery {
+                    reason: format!("owner '{}' is not a valid Pubkey: {}", owner_str, e),
+                }
+            })?;
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                SATELLITE_OWNER_OFFSET,
+                owner.to_bytes().to_vec(),
+            )));
+        }
+
+        if let Some(norad_id) = self.norad_id {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                SATELLITE_NORAD_ID_OFFSET,
+                norad_id.to_le_bytes().to_vec(),
+            )));
+        }
+
+        // Borsh encodes fieldless enums as a single variant index byte
+        if let Some(maneuver_type) = self.maneuver_type {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                SATELLITE_MANEUVER_TYPE_OFFSET,
+                vec![maneuver_type as u8],
+            )));
+        }
+
+        if let Some(operation_status) = self.operation_status {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                SATELLITE_OPERATION_STATUS_OFFSET,
+                vec![operation_status as u8],
+            )));
+        }
+
+        Ok(filters)
+    }
+
+    // Filters on the padded string fields, applied after decoding
+    pub fn matches(&self, satellite: &SatelliteApiResponse) -> bool {
+        if let Some(name) = &self.name {
+            if !satellite.name.to_lowercase().contains(&name.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(country) = &self.country {
+            if !satellite.country.eq_ignore_ascii_case(country) {
+                return false;
+            }
+        }
+        if let Some(orbit_type) = &self.orbit_type {
+            if !satellite.orbit_type.eq_ignore_ascii_case(orbit_type) {
+                return false;
+            }
+        }
+        true
     }
 }
 
 #[debug_handler]
-pub async fn get_satellite_from_norad_id(
-    Path((user_authority_str, registry_authority_str, norad_id_str)): Path<(
-        String,
-        String,
-        String,
-    )>,
+pub async fn get_all_satellites(
+    ValidQuery(query): ValidQuery<SatelliteListQuery>,
     State(app_state): State<Arc<AppState>>,
-) -> Result<Json<SatelliteApiResponse>, StatusCode> {
-    // Parse the string parameters into their correct types
-    let user_authority_pubkey = Pubkey::from_str(&user_authority_str).map_err(|e| {
-        eprintln!(
-            "Invalid user authority Pubkey: {}. Error: {}",
-            user_authority_str, e
-        );
-        StatusCode::BAD_REQUEST
-    })?;
+) -> Result<Json<Vec<SatelliteApiResponse>>, ApiError> {
+    println!("Getting all satellites matching {:?}", query);
 
-    let registry_authority_pubkey = Pubkey::from_str(&registry_authority_str).map_err(|e| {
-        eprintln!(
-            "Invalid registry authority Pubkey: {}. Error: {}",
-            registry_authority_str, e
-        );
-        StatusCode::BAD_REQUEST
-    })?;
+    let filters = query.rpc_filters()?;
+    let includes = SatelliteIncludes::parse(query.include.as_deref())?;
 
-    let norad_id: u64 = norad_id_str.parse().map_err(|e| {
-        eprintln!("Invalid NORAD ID: {}. Error: {}", norad_id_str, e);
-        StatusCode::BAD_REQUEST
-    })?;
+    let accounts = app_state
+        .account_source
+        .get_program_accounts(&app_state.program_id, filters)
+        .await
+        .map_err(|e| {
+            eprintln!(
+                "Error fetching program accounts for {}: {:?}",
+                app_state.program_id, e
+            );
+            ApiError::rpc(&e, Some(app_state.program_id))
+        })?;
+
+    let satellites = accounts
+        .into_iter()
+        .filter_map(|(pubkey, account)| {
+            // the rpc filter should already guarantee this, but don't trust it blindly
+            match decode_satellite_account(&pubkey, &account, &app_state.program_id) {
+                Ok(satellite) => Some(SatelliteApiResponse::from(satellite))
+                    .filter(|satellite| query.matches(satellite))
+                    .map(|satellite| satellite.with_includes(&includes)),
+                Err(e) => {
+                    eprintln!("Skipping account {}: {}", pubkey, e.message());
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    println!("Found {} satellites", satellites.len());
+
+    Ok(Json(satellites))
+}
 
+// Derive the PDA for a set of seeds and return the decoded Satellite, from the cache when a
+// fresh entry exists and from the account source otherwise
+pub(crate) async fn fetch_satellite(
+    app_state: &AppState,
+    seeds: &SatelliteSeeds,
+) -> Result<Arc<CachedSatellite>, ApiError> {
     // derive pda
-    let (pda_pubkey, _bump) = Pubkey::find_program_address(
-        &[
-            b"satellite",
-            user_authority_pubkey.as_ref(),
-            registry_authority_pubkey.as_ref(),
-            &norad_id.to_le_bytes(), // Convert u32 to little-endian bytes
-        ],
-        &app_state.program_id, // Use the program ID from your AppState
-    );
+    let (pda_pubkey, _bump) = seeds.pda(&app_state.program_id);
 
     println!("Derived Satellite PDA Pubkey: {}", pda_pubkey);
 
-    // fetch account details
-    let account = app_state.rpc_client.get_account(&pda_pubkey);
-
-    match account {
-        Ok(account) => {
-            // 3. Deserialize the account data using Borsh
-            // Be very careful that SatelliteProgramAccount exactly matches the on-chain layout.
-            let data_slice = &account.data[8..];
-            let satellite_data: Satellite =
-                BorshDeserialize::try_from_slice(data_slice).map_err(|e| {
-                    eprintln!(
-                        "Failed to deserialize Satellite account data for PDA {}: {:?}",
-                        pda_pubkey, e
-                    );
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-            println!(
-                "Successfully deserialized Satellite data: {:?}",
-                satellite_data
-            );
-            Ok(Json(satellite_data.into()))
-        }
-        Err(e) => {
+    if let Some(cached) = app_state.satellite_cache.get(&pda_pubkey) {
+        println!(
+            "Serving Satellite PDA {} from cache (slot {})",
+            pda_pubkey, cached.slot
+        );
+        return Ok(cached);
+    }
+
+    // fetch account details; a missing account comes back as None rather than an error
+    let response = app_state
+        .account_source
+        .get_account(&pda_pubkey)
+        .await
+        .map_err(|e| {
             eprintln!(
                 "Error fetching account data for Satellite PDA {}: {:?}",
                 pda_pubkey, e
             );
-            // Handle different RPC errors:
-            // Check if the error indicates the account was not found
-            if e.to_string().contains("AccountNotFound") {
-                Err(StatusCode::NOT_FOUND) // Return 404 if account not found
-            } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR) // Generic 500 for other RPC errors
+            ApiError::rpc(&e, Some(pda_pubkey))
+        })?;
+    let slot = response.context.slot;
+    let account = response.value.ok_or_else(|| {
+        eprintln!("Satellite PDA {} does not exist", pda_pubkey);
+        ApiError::AccountNotFound {
+            address: pda_pubkey,
+        }
+    })?;
+
+    // Verify and deserialize the account data using Borsh
+    // Be very careful that Satellite exactly matches the on-chain layout.
+    let satellite_data = decode_satellite_account(&pda_pubkey, &account, &app_state.program_id)
+        .map_err(|e| {
+            eprintln!(
+                "Failed to decode Satellite account data for PDA {}: {}",
+                pda_pubkey,
+                e.message()
+            );
+            ApiError::from(e)
+        })?;
+    println!(
+        "Successfully deserialized Satellite data: {:?}",
+        satellite_data
+    );
+    Ok(app_state.satellite_cache.insert(CachedSatellite::new(
+        pda_pubkey,
+        satellite_data.into(),
+        slot,
+        &account.data,
+    )))
+}
+
+// Representations of a single satellite, picked by a suffix on the NORAD ID segment since
+// the router cannot match `{norad_id}.tle` directly
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SatelliteFormat {
+    Json,
+    Tle,
+    OmmJson,
+}
+
+impl SatelliteFormat {
+    pub fn split(norad_id_str: &str) -> (&str, SatelliteFormat) {
+        if let Some(norad_id_str) = norad_id_str.strip_suffix(".tle") {
+            (norad_id_str, SatelliteFormat::Tle)
+        } else if let Some(norad_id_str) = norad_id_str.strip_suffix(".omm.json") {
+            (norad_id_str, SatelliteFormat::OmmJson)
+        } else {
+            (norad_id_str, SatelliteFormat::Json)
+        }
+    }
+
+    // names the response body, so each representation gets its own ETag
+    pub fn representation(&self, includes: &SatelliteIncludes) -> &'static str {
+        match (self, includes.derived) {
+            (SatelliteFormat::Json, false) => "json",
+            (SatelliteFormat::Json, true) => "json+derived",
+            (SatelliteFormat::Tle, _) => "tle",
+            (SatelliteFormat::OmmJson, _) => "omm",
+        }
+    }
+}
+
+#[debug_handler]
+pub async fn get_satellite_from_norad_id(
+    SatelliteRepresentationPath { seeds, format }: SatelliteRepresentationPath,
+    ValidQuery(query): ValidQuery<SatelliteQuery>,
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let includes = SatelliteIncludes::parse(query.include.as_deref())?;
+
+    let cached = fetch_satellite(&app_state, &seeds).await?;
+    let etag = cached.etag(format.representation(&includes));
+    let headers = caching_headers(&app_state.satellite_cache, &cached, &etag);
+    // the client already holds this exact representation
+    if if_none_match(&request_headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    let satellite = cached.satellite.clone();
+    match format {
+        SatelliteFormat::Json => {
+            Ok((headers, Json(satellite.with_includes(&includes))).into_response())
+        }
+        SatelliteFormat::Tle => Ok((
+            headers,
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            render_tle(&satellite)?,
+        )
+            .into_response()),
+        SatelliteFormat::OmmJson => Ok((headers, Json(render_omm(&satellite)?)).into_response()),
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SatellitePdaQuery {
+    // derive for another deployment of the program than the configured one
+    pub program_id: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SatellitePdaApiResponse {
+    pub address: String,
+    pub bump: u8,
+    pub program_id: String,
+    pub user_authority: String,
+    pub registry_authority: String,
+    pub norad_id: u64,
+}
+
+// Pure derivation, no rpc call; lets clients cross-check their own seed handling
+#[debug_handler]
+pub async fn get_satellite_pda(
+    SatellitePath(seeds): SatellitePath,
+    ValidQuery(query): ValidQuery<SatellitePdaQuery>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<SatellitePdaApiResponse>, ApiError> {
+    let program_id = match &query.program_id {
+        Some(program_id_str) => {
+            Pubkey::from_str(program_id_str).map_err(|e| ApiError::InvalidQuery {
+                reason: format!(
+                    "program_id '{}' is not a valid Pubkey: {}",
+                    program_id_str, e
+                ),
+            })?
+        }
+        None => app_state.program_id,
+    };
+
+    let (address, bump) = seeds.pda(&program_id);
+    Ok(Json(SatellitePdaApiResponse {
+        address: address.to_string(),
+        bump,
+        program_id: program_id.to_string(),
+        user_authority: seeds.user_authority.to_string(),
+        registry_authority: seeds.registry_authority.to_string(),
+        norad_id: seeds.norad_id,
+    }))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // 2023-11-14T22:13:20Z, the epoch every test orbit starts from
+    pub(crate) const LAUNCH_DATE: i64 = 1_700_000_000;
+
+    // A #[max_len(30)] String as the program lays it out: u32 length, then the bytes, zero padded
+    pub(crate) fn padded(value: &str) -> [u8; 34] {
+        let mut bytes = [0u8; 34];
+        bytes[..4].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        bytes[4..4 + value.len()].copy_from_slice(value.as_bytes());
+        bytes
+    }
+
+    // A circular orbit 400 km above the equator at the ISS inclination
+    pub(crate) fn test_satellite(
+        owner: Pubkey,
+        norad_id: u64,
+        name: &str,
+        operation_status: OperationStatus,
+    ) -> Satellite {
+        Satellite {
+            owner,
+            name: padded(name),
+            country: padded("US"),
+            norad_id,
+            launch_date: LAUNCH_DATE,
+            mint_date: LAUNCH_DATE,
+            orbit_type: padded("LEO"),
+            inclination: 51.6,
+            altitude: 400.0,
+            semi_major_axis: 6_778.137,
+            eccentricity: 0.0,
+            raan: 0.0,
+            arg_of_periapsis: 0.0,
+            maneuver_type: ManeuverType::StationKeeping,
+            operation_status,
+        }
+    }
+
+    pub(crate) fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+        Account {
+            lamports: 1_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    // The account the program would create for `satellite`
+    pub(crate) fn satellite_account(program_id: &Pubkey, satellite: &Satellite) -> Account {
+        let mut data = satellite_discriminator().to_vec();
+        data.extend(borsh::to_vec(satellite).unwrap());
+        account(*program_id, data)
+    }
+
+    #[test]
+    fn satellite_discriminator_matches_anchor() {
+        // sha256("account:Satellite")[..8], as Anchor writes it
+        assert_eq!(
+            satellite_discriminator(),
+            [123, 122, 8, 54, 164, 73, 204, 179]
+        );
+    }
+
+    #[test]
+    fn rpc_filter_offsets_match_the_account_layout() {
+        let owner = Pubkey::new_unique();
+        let query = SatelliteListQuery {
+            owner: Some(owner.to_string()),
+            norad_id: Some(25544),
+            operation_status: Some(OperationStatus::Offline),
+            maneuver_type: Some(ManeuverType::EndOfLife),
+            ..SatelliteListQuery::default()
+        };
+
+        // write every filter into an otherwise zeroed account and decode it
+        let mut data = vec![0u8; SATELLITE_OPERATION_STATUS_OFFSET + 1];
+        for filter in query.rpc_filters().unwrap() {
+            let RpcFilterType::Memcmp(memcmp) = filter else {
+                panic!("expected only memcmp filters");
+            };
+            let bytes = memcmp.bytes().unwrap();
+            data[memcmp.offset()..memcmp.offset() + bytes.len()].copy_from_slice(&bytes);
+        }
+        assert_eq!(
+            data[..SATELLITE_DISCRIMINATOR_LEN],
+            satellite_discriminator()
+        );
+        let satellite = Satellite::try_from_slice(&data[SATELLITE_DISCRIMINATOR_LEN..]).unwrap();
+        assert_eq!(satellite.owner, owner);
+        assert_eq!(satellite.norad_id, 25544);
+        assert_eq!(satellite.maneuver_type, ManeuverType::EndOfLife);
+        assert_eq!(satellite.operation_status, OperationStatus::Offline);
+    }
+
+    #[test]
+    fn invalid_owner_filter_is_rejected() {
+        let query = SatelliteListQuery {
+            owner: Some("not-a-pubkey".to_string()),
+            ..SatelliteListQuery::default()
+        };
+        assert!(query.rpc_filters().is_err());
+    }
+
+    #[test]
+    fn string_filters_ignore_case() {
+        let satellite = SatelliteApiResponse::from(test_satellite(
+            Pubkey::new_unique(),
+            1,
+            "Hubble",
+            OperationStatus::Active,
+        ));
+        let query = |name: Option<&str>, country: Option<&str>, orbit_type: Option<&str>| {
+            SatelliteListQuery {
+                name: name.map(str::to_string),
+                country: country.map(str::to_string),
+                orbit_type: orbit_type.map(str::to_string),
+                ..SatelliteListQuery::default()
             }
+        };
+        assert!(query(None, None, None).matches(&satellite));
+        // names match on a substring, country and orbit type on the whole value
+        assert!(query(Some("HUB"), Some("us"), Some("leo")).matches(&satellite));
+        assert!(!query(Some("webb"), None, None).matches(&satellite));
+        assert!(!query(None, Some("u"), None).matches(&satellite));
+        assert!(!query(None, None, Some("GEO")).matches(&satellite));
+    }
+
+    #[test]
+    fn decode_checks_owner_and_discriminator_first() {
+        let (address, program_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // a zeroed body is a valid Satellite, with room left over as Anchor accounts may have
+        let mut data = satellite_discriminator().to_vec();
+        data.resize(SATELLITE_OPERATION_STATUS_OFFSET + 64, 0);
+
+        let satellite =
+            decode_satellite_account(&address, &account(program_id, data.clone()), &program_id)
+                .unwrap();
+        assert_eq!(satellite.norad_id, 0);
+
+        let stranger = Pubkey::new_unique();
+        assert!(matches!(
+            decode_satellite_account(&address, &account(stranger, data.clone()), &program_id),
+            Err(SatelliteAccountError::OwnerMismatch { actual, .. }) if actual == stranger
+        ));
+        assert!(matches!(
+            decode_satellite_account(&address, &account(program_id, vec![1, 2, 3]), &program_id),
+            Err(SatelliteAccountError::DataTooShort { len: 3, .. })
+        ));
+
+        let mut other_account = data.clone();
+        other_account[0] ^= 0xff;
+        let error =
+            decode_satellite_account(&address, &account(program_id, other_account), &program_id)
+                .unwrap_err();
+        assert!(matches!(
+            error,
+            SatelliteAccountError::DiscriminatorMismatch { .. }
+        ));
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // the discriminator matches but the body is cut short
+        data.truncate(SATELLITE_NORAD_ID_OFFSET);
+        assert!(matches!(
+            decode_satellite_account(&address, &account(program_id, data), &program_id),
+            Err(SatelliteAccountError::InvalidData { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn satellite_path_names_the_segment_that_failed() {
+        use axum::{
+            body::{to_bytes, Body},
+            http::Request,
+            routing::get,
+            Router,
+        };
+        use tower::ServiceExt;
+
+        let router = Router::new().route(
+            "/satellites/{user_authority}/{registry_authority}/{norad_id}",
+            get(|SatellitePath(seeds): SatellitePath| async move { Json(seeds.norad_id) }),
+        );
+        let send = |uri: String| {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+        let (user, registry) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let (status, body) = send(format!("/satellites/{}/{}/25544", user, registry)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, 25544);
+
+        for (uri, parameter) in [
+            (
+                format!("/satellites/nope/{}/25544", registry),
+                "user_authority",
+            ),
+            (
+                format!("/satellites/{}/nope/25544", user),
+                "registry_authority",
+            ),
+            (format!("/satellites/{}/{}/ISS", user, registry), "norad_id"),
+        ] {
+            let (status, body) = send(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["code"], "invalid_path_parameter");
+            assert_eq!(body["details"]["parameter"], parameter);
         }
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn slow_rpc_lookups_do_not_block_each_other() {
+        use std::time::{Duration, Instant};
+
+        use axum::{routing::post, Router};
+        use tokio::{net::TcpListener, task::JoinSet};
+
+        use crate::{
+            AppConfig, InMemoryFruitRepository, RawConfig, SatelliteCache, SatelliteEvents,
+            SatelliteStreams, EVENT_CHANNEL_CAPACITY, SATELLITE_CACHE_MAX_ENTRIES,
+            STREAM_CHANNEL_CAPACITY,
+        };
+
+        const DELAY: Duration = Duration::from_millis(250);
+        const LOOKUPS: usize = 32;
+
+        // a stand-in rpc node that takes its time to report every account as missing
+        let rpc_node = Router::new().route(
+            "/",
+            post(|Json(request): Json<Value>| async move {
+                tokio::time::sleep(DELAY).await;
+                Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": { "context": { "slot": 1 }, "value": null },
+                }))
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, rpc_node).await });
+
+        let config = AppConfig::from_raw(RawConfig {
+            rpc_url: Some(rpc_url),
+            // never connected to, lookups don't subscribe
+            ws_url: Some("ws://127.0.0.1:9".to_string()),
+            ..RawConfig::default()
+        })
+        .unwrap();
+        let app_state = Arc::new(AppState {
+            program_id: Pubkey::new_unique(),
+            account_source: Arc::new(crate::rpc_client(
+                config.rpc_url.clone(),
+                config.request_timeout,
+                config.commitment,
+            )),
+            satellite_cache: SatelliteCache::new(config.cache_ttl, SATELLITE_CACHE_MAX_ENTRIES),
+            satellite_streams: SatelliteStreams::new(STREAM_CHANNEL_CAPACITY),
+            satellite_events: SatelliteEvents::new(EVENT_CHANNEL_CAPACITY),
+            fruits: Arc::new(InMemoryFruitRepository::with_default_fruits()),
+            config,
+        });
+        let started = Instant::now();
+        let mut lookups = JoinSet::new();
+        for norad_id in 0..LOOKUPS as u64 {
+            let seeds = SatelliteSeeds {
+                user_authority: Pubkey::new_unique(),
+                registry_authority: Pubkey::new_unique(),
+                norad_id,
+            };
+            lookups.spawn(get_satellite_from_norad_id(
+                SatelliteRepresentationPath {
+                    seeds,
+                    format: SatelliteFormat::Json,
+                },
+                ValidQuery(SatelliteQuery::default()),
+                State(app_state.clone()),
+                HeaderMap::new(),
+            ));
+        }
+        while let Some(result) = lookups.join_next().await {
+            assert!(matches!(
+                result.unwrap(),
+                Err(ApiError::AccountNotFound { .. })
+            ));
+        }
+
+        // one blocked worker per lookup would take LOOKUPS / 2 round trips
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed < DELAY * 3,
+            "{} lookups took {:?}",
+            LOOKUPS,
+            elapsed
+        );
+    }
 }
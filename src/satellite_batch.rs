@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use axum::{debug_handler, extract::rejection::JsonRejection, extract::State, Json};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    decode_satellite_account, parse_body_pubkey, ApiError, ApiErrorBody, AppState, RpcErrorClass,
+    SatelliteApiResponse, SatelliteIncludes, SatelliteQuery, SatelliteSeeds, ValidQuery,
+};
+
+// getMultipleAccounts accepts at most 100 addresses per call
+pub const GET_MULTIPLE_ACCOUNTS_CHUNK_SIZE: usize = 100;
+pub const MAX_BATCH_SIZE: usize = 1_000;
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SatelliteBatchItem {
+    pub user_authority: String,
+    pub registry_authority: String,
+    pub norad_id: u64,
+}
+
+impl SatelliteBatchItem {
+    fn seeds(&self) -> Result<SatelliteSeeds, ApiError> {
+        Ok(SatelliteSeeds {
+            user_authority: parse_body_pubkey("user_authority", &self.user_authority)?,
+            registry_authority: parse_body_pubkey("registry_authority", &self.registry_authority)?,
+            norad_id: self.norad_id,
+        })
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SatelliteBatchRequest {
+    pub satellites: Vec<SatelliteBatchItem>,
+}
+
+// One entry per requested item, in request order; exactly one of satellite and error is set
+#[derive(Debug, serde::Serialize)]
+pub struct SatelliteBatchResult {
+    #[serde(flatten)]
+    pub item: SatelliteBatchItem,
+    pub address: Option<String>,
+    pub status: u16,
+    pub satellite: Option<SatelliteApiResponse>,
+    pub error: Option<ApiErrorBody>,
+}
+
+impl SatelliteBatchResult {
+    fn new(
+        item: SatelliteBatchItem,
+        address: Option<Pubkey>,
+        result: Result<SatelliteApiResponse, ApiError>,
+    ) -> Self {
+        let address = address.map(|address| address.to_string());
+        match result {
+            Ok(satellite) => SatelliteBatchResult {
+                item,
+                address,
+                status: 200,
+                satellite: Some(satellite),
+                error: None,
+            },
+            Err(e) => SatelliteBatchResult {
+                item,
+                address,
+                status: e.status_code().as_u16(),
+                satellite: None,
+                error: Some(ApiErrorBody::from(&e)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SatelliteBatchApiResponse {
+    pub results: Vec<SatelliteBatchResult>,
+}
+
+#[debug_handler]
+pub async fn get_satellites_batch(
+    ValidQuery(query): ValidQuery<SatelliteQuery>,
+    State(app_state): State<Arc<AppState>>,
+    body: Result<Json<SatelliteBatchRequest>, JsonRejection>,
+) -> Result<Json<SatelliteBatchApiResponse>, ApiError> {
+    let includes = SatelliteIncludes::parse(query.include.as_deref())?;
+    let Json(request) = body.map_err(|e| ApiError::InvalidBody {
+        reason: e.body_text(),
+    })?;
+    if request.satellites.is_empty() || request.satellites.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::Validation {
+            field: "satellites",
+            reason: format!(
+                "must list between 1 and {} satellites, got {}",
+                MAX_BATCH_SIZE,
+                request.satellites.len()
+            ),
+        });
+    }
+    println!(
+        "Looking up a batch of {} satellites",
+        request.satellites.len()
+    );
+
+    // items with malformed seeds fail on their own without an address to fetch
+    let addresses = request
+        .satellites
+        .iter()
+        .map(|item| item.seeds().map(|seeds| seeds.pda(&app_state.program_id).0))
+        .collect::<Vec<_>>();
+    let to_fetch = addresses
+        .iter()
+        .filter_map(|address| address.as_ref().ok().copied())
+        .collect::<Vec<_>>();
+
+    let mut accounts = Vec::with_capacity(to_fetch.len());
+    for chunk in to_fetch.chunks(GET_MULTIPLE_ACCOUNTS_CHUNK_SIZE) {
+        let response = app_state
+            .account_source
+            .get_multiple_accounts(chunk)
+            .await
+            .map_err(|e| {
+                eprintln!("Error fetching {} satellite accounts: {:?}", chunk.len(), e);
+                ApiError::rpc(&e, None)
+            })?;
+        // accounts are matched to addresses by position, so a reply of the wrong length would
+        // hand them to the wrong requests
+        if response.value.len() != chunk.len() {
+            let message = format!(
+                "getMultipleAccounts returned {} accounts for {} addresses",
+                response.value.len(),
+                chunk.len()
+            );
+            eprintln!("{}", message);
+            return Err(ApiError::Rpc {
+                class: RpcErrorClass::MalformedResponse,
+                message,
+                address: None,
+                retry_after: None,
+            });
+        }
+        accounts.extend(response.value);
+    }
+
+    let mut accounts = accounts.into_iter();
+    let results = request
+        .satellites
+        .into_iter()
+        .zip(addresses)
+        .map(|(item, address)| match address {
+            Err(e) => SatelliteBatchResult::new(item, None, Err(e)),
+            Ok(address) => {
+                let result = accounts
+                    .next()
+                    .flatten()
+                    .ok_or(ApiError::AccountNotFound { address })
+                    .and_then(|account| {
+                        decode_satellite_account(&address, &account, &app_state.program_id)
+                            .map_err(ApiError::from)
+                    })
+                    .map(|satellite| {
+                        SatelliteApiResponse::from(satellite).with_includes(&includes)
+                    });
+                SatelliteBatchResult::new(item, Some(address), result)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "Found {} of {} satellites",
+        results
+            .iter()
+            .filter(|result| result.satellite.is_some())
+            .count(),
+        results.len()
+    );
+
+    Ok(Json(SatelliteBatchApiResponse { results }))
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use solana_sdk::{
+    hash::{hash, Hash},
+    pubkey::Pubkey,
+};
+
+use crate::SatelliteApiResponse;
+
+// enough for a large registry while keeping memory bounded when clients walk unknown PDAs
+pub const SATELLITE_CACHE_MAX_ENTRIES: usize = 10_000;
+
+// A decoded satellite account together with where and when it was read
+#[derive(Debug)]
+pub struct CachedSatellite {
+    pub address: Pubkey,
+    pub satellite: SatelliteApiResponse,
+    // slot of the rpc response the account was read from
+    pub slot: u64,
+    // sha256 of the raw account data, so any on-chain change gives a new ETag
+    pub data_hash: Hash,
+    fetched_at: Instant,
+}
+
+impl CachedSatellite {
+    pub fn new(address: Pubkey, satellite: SatelliteApiResponse, slot: u64, data: &[u8]) -> Self {
+        CachedSatellite {
+            address,
+            satellite,
+            slot,
+            data_hash: hash(data),
+            fetched_at: Instant::now(),
+        }
+    }
+
+    // Strong ETag for one representation of the account, e.g. "json" or "tle"
+    pub fn etag(&self, representation: &str) -> String {
+        format!("\"{}-{}\"", self.data_hash, representation)
+    }
+}
+
+// In-process cache of decoded satellites keyed by PDA. Entries expire after the configured
+// TTL and are never replaced by a read from an older slot. Once it holds max_entries, expired
+// entries are dropped to make room and then the oldest ones.
+#[derive(Debug)]
+pub struct SatelliteCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: RwLock<HashMap<Pubkey, Arc<CachedSatellite>>>,
+}
+
+impl SatelliteCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        SatelliteCache {
+            ttl,
+            max_entries,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    fn is_fresh(&self, entry: &CachedSatellite) -> bool {
+        entry.fetched_at.elapsed() < self.ttl
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<Arc<CachedSatellite>> {
+        let entries = self.entries.read().unwrap();
+        entries
+            .get(address)
+            .filter(|entry| self.is_fresh(entry))
+            .cloned()
+    }
+
+    // Store a fresh read and return the entry callers should serve, which is the cached one
+    // if the rpc node answered from a slot behind what is already cached
+    pub fn insert(&self, entry: CachedSatellite) -> Arc<CachedSatellite> {
+        let entry = Arc::new(entry);
+        if !self.is_enabled() {
+            return entry;
+        }
+        let mut entries = self.entries.write().unwrap();
+        match entries.get(&entry.address) {
+            Some(existing) if self.is_fresh(existing) && existing.slot > entry.slot => {
+                return existing.clone();
+            }
+            Some(_) => {}
+            None => self.make_room(&mut entries),
+        }
+        entries.insert(entry.address, entry.clone());
+        entry
+    }
+
+    // Free a slot for one more entry: expired entries first, then the oldest fresh one
+    fn make_room(&self, entries: &mut HashMap<Pubkey, Arc<CachedSatellite>>) {
+        if entries.len() < self.max_entries {
+            return;
+        }
+        entries.retain(|_, entry| self.is_fresh(entry));
+        while entries.len() >= self.max_entries {
+            let Some(oldest) = entries
+                .values()
+                .min_by_key(|entry| entry.fetched_at)
+                .map(|entry| entry.address)
+            else {
+                return;
+            };
+            entries.remove(&oldest);
+        }
+    }
+
+    pub fn cache_control(&self, entry: &CachedSatellite) -> String {
+        if !self.is_enabled() {
+            return "no-cache".to_string();
+        }
+        let remaining = self.ttl.saturating_sub(entry.fetched_at.elapsed());
+        format!("public, max-age={}", remaining.as_secs())
+    }
+}
+
+// If-None-Match uses weak comparison, so W/"x" matches "x"; "*" matches any current entity
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim())
+        .any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        })
+}
+
+pub fn caching_headers(cache: &SatelliteCache, entry: &CachedSatellite, etag: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&cache.cache_control(entry)) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+    use crate::{satellite::tests::test_satellite, OperationStatus};
+
+    fn entry(slot: u64) -> CachedSatellite {
+        let satellite = test_satellite(Pubkey::new_unique(), 25544, "ISS", OperationStatus::Active);
+        CachedSatellite::new(Pubkey::new_unique(), satellite.into(), slot, &[])
+    }
+
+    #[test]
+    fn full_cache_evicts_the_oldest_entry() {
+        let cache = SatelliteCache::new(Duration::from_secs(60), 2);
+        // spaced out so their fetch times are ordered
+        let insert = || {
+            sleep(Duration::from_millis(1));
+            cache.insert(entry(1)).address
+        };
+        let (first, second, third) = (insert(), insert(), insert());
+        assert!(cache.get(&first).is_none());
+        assert!(cache.get(&second).is_some());
+        assert!(cache.get(&third).is_some());
+        assert_eq!(cache.entries.read().unwrap().len(), 2);
+
+        // refreshing a cached address takes no extra room
+        let mut refreshed = entry(2);
+        refreshed.address = second;
+        cache.insert(refreshed);
+        assert!(cache.get(&third).is_some());
+    }
+
+    #[test]
+    fn full_cache_drops_expired_entries_first() {
+        let cache = SatelliteCache::new(Duration::from_millis(50), 3);
+        cache.insert(entry(1));
+        cache.insert(entry(1));
+        sleep(Duration::from_millis(60));
+        let fresh = cache.insert(entry(1)).address;
+        cache.insert(entry(1));
+        let entries = cache.entries.read().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.contains_key(&fresh));
+    }
+}
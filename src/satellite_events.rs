@@ -0,0 +1,282 @@
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    str::FromStr,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+
+use axum::{
+    debug_handler,
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{stream, Stream};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_response::{Response as RpcResponse, RpcKeyedAccount},
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::sync::broadcast;
+
+use crate::{
+    decode_satellite_account, run_upstream, ApiError, AppState, CachedSatellite, FanOut,
+    OperationStatus, SatelliteApiResponse, SatelliteListQuery, Subscription, Upstream,
+};
+
+// the feed covers the whole registry, so give slow clients more room than a single satellite
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SatelliteEventKind {
+    Created,
+    Updated,
+    StatusChanged,
+}
+
+impl SatelliteEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SatelliteEventKind::Created => "created",
+            SatelliteEventKind::Updated => "updated",
+            SatelliteEventKind::StatusChanged => "status_changed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SatelliteEvent {
+    pub kind: SatelliteEventKind,
+    pub address: String,
+    pub slot: u64,
+    // every field for created, otherwise only the ones that differ from the last known value
+    pub changed_fields: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<OperationStatus>,
+    pub satellite: SatelliteApiResponse,
+}
+
+// Field names whose serialized values differ between two versions of a satellite
+fn changed_fields(
+    previous: Option<&SatelliteApiResponse>,
+    current: &SatelliteApiResponse,
+) -> Vec<String> {
+    let as_object = |satellite: &SatelliteApiResponse| match serde_json::to_value(satellite) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    let current = as_object(current);
+    let previous = previous.map(as_object).unwrap_or_default();
+    current
+        .iter()
+        .filter(|(field, value)| previous.get(*field) != Some(*value))
+        .map(|(field, _)| field.clone())
+        .collect()
+}
+
+impl SatelliteEvent {
+    // None when nothing observable changed, e.g. a rewrite with identical data
+    fn diff(
+        address: Pubkey,
+        slot: u64,
+        previous: Option<&SatelliteApiResponse>,
+        current: &SatelliteApiResponse,
+    ) -> Option<Self> {
+        let changed_fields = changed_fields(previous, current);
+        if changed_fields.is_empty() {
+            return None;
+        }
+        let (kind, previous_status) = match previous {
+            None => (SatelliteEventKind::Created, None),
+            Some(previous) if previous.operation_status != current.operation_status => (
+                SatelliteEventKind::StatusChanged,
+                Some(previous.operation_status),
+            ),
+            Some(_) => (SatelliteEventKind::Updated, None),
+        };
+        Some(SatelliteEvent {
+            kind,
+            address: address.to_string(),
+            slot,
+            changed_fields,
+            previous_status,
+            satellite: current.clone(),
+        })
+    }
+
+    fn to_sse(&self) -> Event {
+        Event::default()
+            .event(self.kind.as_str())
+            .json_data(self)
+            .unwrap_or_default()
+    }
+}
+
+// The registry-wide feed: one broadcast channel fed by a single programSubscribe while
+// anybody is listening
+pub type SatelliteEvents = FanOut<(), Arc<SatelliteEvent>>;
+
+// programSubscribe on the whole registry, keeping the last decoded value of every satellite
+// to diff notifications against
+struct RegistryUpstream {
+    app_state: Arc<AppState>,
+    sender: broadcast::Sender<Arc<SatelliteEvent>>,
+    known: HashMap<Pubkey, SatelliteApiResponse>,
+    // false until the first read of the registry, which only establishes the baseline; after
+    // that a reconnect reports what changed in the meantime
+    emit: bool,
+}
+
+impl RegistryUpstream {
+    fn apply(&mut self, address: Pubkey, slot: u64, account: &Account, emit: bool) {
+        let app_state = &self.app_state;
+        let satellite = match decode_satellite_account(&address, account, &app_state.program_id) {
+            Ok(satellite) => SatelliteApiResponse::from(satellite),
+            Err(e) => {
+                // closed or foreign accounts owned by the program are not satellites
+                if self.known.remove(&address).is_some() {
+                    eprintln!("Satellite {} is gone: {}", address, e.message());
+                }
+                return;
+            }
+        };
+        // keep single-satellite reads in step with what the feed has seen
+        app_state.satellite_cache.insert(CachedSatellite::new(
+            address,
+            satellite.clone(),
+            slot,
+            &account.data,
+        ));
+        let event = SatelliteEvent::diff(address, slot, self.known.get(&address), &satellite);
+        self.known.insert(address, satellite);
+        if let Some(event) = event.filter(|_| emit) {
+            println!("Satellite {} {}", address, event.kind.as_str());
+            // no receivers just means the last client left; the idle check cleans up
+            let _ = self.sender.send(Arc::new(event));
+        }
+    }
+
+    // Read the whole registry and diff it against what is known
+    async fn reconcile(&mut self) -> Result<(), ApiError> {
+        let app_state = self.app_state.clone();
+        let filters = SatelliteListQuery::default().rpc_filters()?;
+        let slot = app_state
+            .account_source
+            .get_slot()
+            .await
+            .map_err(|e| ApiError::rpc(&e, None))?;
+        let accounts = app_state
+            .account_source
+            .get_program_accounts(&app_state.program_id, filters)
+            .await
+            .map_err(|e| ApiError::rpc(&e, Some(app_state.program_id)))?;
+
+        let present = accounts
+            .iter()
+            .map(|(address, _)| *address)
+            .collect::<HashSet<_>>();
+        self.known.retain(|address, _| present.contains(address));
+        for (address, account) in &accounts {
+            self.apply(*address, slot, account, self.emit);
+        }
+        self.emit = true;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Upstream for RegistryUpstream {
+    type Notification = RpcResponse<RpcKeyedAccount>;
+
+    fn describe(&self) -> String {
+        format!("program {}", self.app_state.program_id)
+    }
+
+    fn release_if_idle(&self) -> bool {
+        self.app_state
+            .satellite_events
+            .release_if_idle(&(), &self.sender)
+    }
+
+    async fn subscribe<'a>(
+        &self,
+        client: &'a PubsubClient,
+    ) -> Result<Subscription<'a, Self::Notification>, String> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(
+                SatelliteListQuery::default()
+                    .rpc_filters()
+                    .map_err(|e| e.message())?,
+            ),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.app_state.config.commitment),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        client
+            .program_subscribe(&self.app_state.program_id, Some(config))
+            .await
+            .map_err(|e| format!("programSubscribe: {}", e))
+    }
+
+    async fn resync(&mut self) -> Result<(), String> {
+        self.reconcile()
+            .await
+            .map_err(|e| format!("reading the registry: {}", e.message()))
+    }
+
+    fn forward(&mut self, response: Self::Notification) {
+        let address = Pubkey::from_str(&response.value.pubkey);
+        match (address, response.value.account.decode::<Account>()) {
+            (Ok(address), Some(account)) => {
+                self.apply(address, response.context.slot, &account, true)
+            }
+            _ => eprintln!(
+                "Undecodable program notification for {}",
+                response.value.pubkey
+            ),
+        }
+    }
+
+    async fn poll(&mut self) {
+        if let Err(e) = self.reconcile().await {
+            eprintln!("Error polling the registry: {}", e.message());
+        }
+    }
+}
+
+#[debug_handler]
+pub async fn get_satellite_events(
+    State(app_state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (receiver, upstream) = app_state.satellite_events.subscribe(());
+    if let Some(sender) = upstream {
+        let upstream = RegistryUpstream {
+            app_state: app_state.clone(),
+            sender,
+            known: HashMap::new(),
+            emit: false,
+        };
+        tokio::spawn(run_upstream(app_state.clone(), upstream));
+    }
+    println!("Event feed client connected");
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((Ok(event.to_sse()), receiver)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("Event feed client skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,234 @@
+use std::{str::FromStr, sync::Arc};
+
+use axum::{debug_handler, extract::State, Json};
+use borsh::BorshDeserialize;
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::{
+    instruction_discriminator, ApiError, AppState, CreateSatelliteArgs, HistoricalTransaction,
+    ManeuverType, OperationStatus, SatellitePath, ValidQuery,
+};
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+pub const MAX_HISTORY_LIMIT: usize = 100;
+// signatures looked at per request, so a PDA buried in failed transactions can't pin a handler
+pub const MAX_HISTORY_SCAN: usize = 1_000;
+// signatures asked for per getSignaturesForAddress call, however many entries are still wanted
+const HISTORY_PAGE_SIZE: usize = MAX_HISTORY_LIMIT;
+// getTransaction calls in flight at once while walking a page of signatures
+const HISTORY_FETCH_CONCURRENCY: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SatelliteInstruction {
+    CreateSatellite,
+    UpdateStatus,
+    RecordManeuver,
+}
+
+impl SatelliteInstruction {
+    pub const ALL: [SatelliteInstruction; 3] = [
+        SatelliteInstruction::CreateSatellite,
+        SatelliteInstruction::UpdateStatus,
+        SatelliteInstruction::RecordManeuver,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SatelliteInstruction::CreateSatellite => "create_satellite",
+            SatelliteInstruction::UpdateStatus => "update_status",
+            SatelliteInstruction::RecordManeuver => "record_maneuver",
+        }
+    }
+
+    fn from_discriminator(discriminator: &[u8]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|instruction| instruction_discriminator(instruction.name()) == discriminator)
+    }
+}
+
+// One instruction that wrote to the satellite, with the values it set
+#[derive(Debug, serde::Serialize)]
+pub struct SatelliteHistoryEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<DateTime<Utc>>,
+    // the user authority the program required to sign the instruction
+    pub signer: String,
+    pub instruction: SatelliteInstruction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_status: Option<OperationStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maneuver_type: Option<ManeuverType>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SatelliteHistoryQuery {
+    // signature of the last transaction on the previous page
+    pub before: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SatelliteHistoryApiResponse {
+    pub address: String,
+    // newest first
+    pub entries: Vec<SatelliteHistoryEntry>,
+    // pass as ?before= for older entries; absent once the history is exhausted
+    pub next_cursor: Option<String>,
+}
+
+// Every top-level instruction in the transaction that the program ran against this PDA.
+// Both the create and update contexts put the satellite first and the signing user
+// authority second.
+fn decode_history_entries(
+    transaction: &HistoricalTransaction,
+    address: &Pubkey,
+    program_id: &Pubkey,
+) -> Vec<SatelliteHistoryEntry> {
+    let key = |index: u8| transaction.account_keys.get(index as usize);
+    let block_time = transaction
+        .block_time
+        .and_then(|block_time| DateTime::from_timestamp(block_time, 0));
+
+    transaction
+        .instructions
+        .iter()
+        .filter(|instruction| key(instruction.program_id_index) == Some(program_id))
+        .filter(|instruction| instruction.accounts.first().copied().and_then(key) == Some(address))
+        .filter_map(|instruction| {
+            let (discriminator, mut args) = instruction.data.split_at_checked(8)?;
+            let kind = SatelliteInstruction::from_discriminator(discriminator)?;
+            let signer = instruction.accounts.get(1).copied().and_then(key)?;
+            let (operation_status, maneuver_type) = match kind {
+                SatelliteInstruction::CreateSatellite => {
+                    CreateSatelliteArgs::deserialize(&mut args)
+                        .map(|args| (Some(args.operation_status), Some(args.maneuver_type)))
+                }
+                SatelliteInstruction::UpdateStatus => {
+                    OperationStatus::deserialize(&mut args).map(|status| (Some(status), None))
+                }
+                SatelliteInstruction::RecordManeuver => {
+                    ManeuverType::deserialize(&mut args).map(|maneuver| (None, Some(maneuver)))
+                }
+            }
+            .map_err(|e| {
+                eprintln!(
+                    "Skipping undecodable {} in {}: {}",
+                    kind.name(),
+                    transaction.signature,
+                    e
+                )
+            })
+            .ok()?;
+            Some(SatelliteHistoryEntry {
+                signature: transaction.signature.to_string(),
+                slot: transaction.slot,
+                block_time,
+                signer: signer.to_string(),
+                instruction: kind,
+                operation_status,
+                maneuver_type,
+            })
+        })
+        .collect()
+}
+
+#[debug_handler]
+pub async fn get_satellite_history(
+    SatellitePath(seeds): SatellitePath,
+    ValidQuery(query): ValidQuery<SatelliteHistoryQuery>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<SatelliteHistoryApiResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    if limit == 0 || limit > MAX_HISTORY_LIMIT {
+        return Err(ApiError::InvalidQuery {
+            reason: format!(
+                "limit {} must be between 1 and {}",
+                limit, MAX_HISTORY_LIMIT
+            ),
+        });
+    }
+    let mut cursor = query
+        .before
+        .as_deref()
+        .map(|before| {
+            Signature::from_str(before).map_err(|e| ApiError::InvalidQuery {
+                reason: format!("before '{}' is not a valid signature: {}", before, e),
+            })
+        })
+        .transpose()?;
+
+    let (address, _bump) = seeds.pda(&app_state.program_id);
+    println!("Walking history of Satellite PDA {}", address);
+
+    let mut entries = Vec::new();
+    let mut scanned = 0;
+    let next_cursor = 'walk: loop {
+        let page = app_state
+            .account_source
+            .get_signatures_for_address(&address, cursor, HISTORY_PAGE_SIZE)
+            .await
+            .map_err(|e| {
+                eprintln!("Error fetching signatures for {}: {:?}", address, e);
+                ApiError::rpc(&e, Some(address))
+            })?;
+
+        let signatures = page
+            .iter()
+            .map(|info| (info.signature, info.failed))
+            .collect::<Vec<_>>();
+        let mut transactions = stream::iter(signatures)
+            .map(|(signature, failed)| {
+                let account_source = app_state.account_source.clone();
+                async move {
+                    // failed transactions changed nothing, so they are never fetched
+                    if failed {
+                        return Ok((signature, None));
+                    }
+                    let transaction = account_source.get_transaction(&signature).await?;
+                    Ok((signature, Some(transaction)))
+                }
+            })
+            .buffered(HISTORY_FETCH_CONCURRENCY);
+        // walked in order so the cursor always sits right after the last entry returned
+        while let Some(fetched) = transactions.next().await {
+            let (signature, transaction) = fetched.map_err(|e| {
+                eprintln!("Error fetching transactions for {}: {:?}", address, e);
+                ApiError::rpc(&e, None)
+            })?;
+            if let Some(transaction) = &transaction {
+                entries.extend(decode_history_entries(
+                    transaction,
+                    &address,
+                    &app_state.program_id,
+                ));
+            }
+            scanned += 1;
+            cursor = Some(signature);
+            // a transaction may hold several instructions, so this can end slightly over limit
+            if entries.len() >= limit || scanned >= MAX_HISTORY_SCAN {
+                break 'walk cursor;
+            }
+        }
+        if page.len() < HISTORY_PAGE_SIZE {
+            break None;
+        }
+    };
+
+    println!(
+        "Found {} history entries for {} in {} transactions",
+        entries.len(),
+        address,
+        scanned
+    );
+
+    Ok(Json(SatelliteHistoryApiResponse {
+        address: address.to_string(),
+        entries,
+        next_cursor: next_cursor.map(|signature| signature.to_string()),
+    }))
+}
@@ -0,0 +1,327 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use axum::{
+    debug_handler,
+    extract::ws::{
+        rejection::WebSocketUpgradeRejection, Message, Utf8Bytes, WebSocket, WebSocketUpgrade,
+    },
+    extract::State,
+    response::Response,
+};
+use futures_util::{SinkExt, StreamExt};
+use solana_account_decoder_client_types::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig,
+    rpc_response::Response as RpcResponse,
+};
+use solana_sdk::{
+    account::Account,
+    hash::{hash, Hash},
+    pubkey::Pubkey,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    decode_satellite_account, fetch_satellite, run_upstream, ApiError, ApiErrorBody, AppState,
+    CachedSatellite, FanOut, SatellitePath, SatelliteSeeds, Subscription, Upstream,
+};
+
+// messages a slow browser may fall behind by before it skips to the latest ones
+pub const STREAM_CHANNEL_CAPACITY: usize = 16;
+
+// One broadcast channel per PDA, shared by every connected client watching that satellite
+pub type SatelliteStreams = FanOut<Pubkey, Utf8Bytes>;
+
+fn error_message(error: &ApiError) -> Utf8Bytes {
+    serde_json::to_string(&ApiErrorBody::from(error))
+        .unwrap_or_default()
+        .into()
+}
+
+fn satellite_message(cached: &CachedSatellite) -> Utf8Bytes {
+    serde_json::to_string(&cached.satellite)
+        .unwrap_or_default()
+        .into()
+}
+
+// Decode a changed account, refresh the cache with it and fan it out
+fn publish(
+    app_state: &AppState,
+    address: Pubkey,
+    slot: u64,
+    account: &Account,
+    sender: &broadcast::Sender<Utf8Bytes>,
+) {
+    let message = match decode_satellite_account(&address, account, &app_state.program_id) {
+        Ok(satellite) => satellite_message(&app_state.satellite_cache.insert(
+            CachedSatellite::new(address, satellite.into(), slot, &account.data),
+        )),
+        Err(e) => {
+            eprintln!(
+                "Streamed account {} did not decode: {}",
+                address,
+                e.message()
+            );
+            error_message(&ApiError::from(e))
+        }
+    };
+    // no receivers just means the last client left; the idle check cleans up
+    let _ = sender.send(message);
+}
+
+// accountSubscribe on a single satellite PDA
+struct AccountUpstream {
+    app_state: Arc<AppState>,
+    address: Pubkey,
+    sender: broadcast::Sender<Utf8Bytes>,
+    // None until the first read has established what the account looked like
+    last_hash: Option<Option<Hash>>,
+}
+
+impl AccountUpstream {
+    // Read the account directly and publish it if it changed since it was last seen
+    async fn refresh(&mut self) -> Result<(), ApiError> {
+        let response = self
+            .app_state
+            .account_source
+            .get_account(&self.address)
+            .await
+            .map_err(|e| ApiError::rpc(&e, Some(self.address)))?;
+        let data_hash = response.value.as_ref().map(|account| hash(&account.data));
+        if self
+            .last_hash
+            .is_some_and(|last_hash| last_hash != data_hash)
+        {
+            if let Some(account) = &response.value {
+                publish(
+                    &self.app_state,
+                    self.address,
+                    response.context.slot,
+                    account,
+                    &self.sender,
+                );
+            }
+        }
+        self.last_hash = Some(data_hash);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Upstream for AccountUpstream {
+    type Notification = RpcResponse<UiAccount>;
+
+    fn describe(&self) -> String {
+        format!("account {}", self.address)
+    }
+
+    fn release_if_idle(&self) -> bool {
+        self.app_state
+            .satellite_streams
+            .release_if_idle(&self.address, &self.sender)
+    }
+
+    async fn subscribe<'a>(
+        &self,
+        client: &'a PubsubClient,
+    ) -> Result<Subscription<'a, Self::Notification>, String> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.app_state.config.commitment),
+            ..RpcAccountInfoConfig::default()
+        };
+        client
+            .account_subscribe(&self.address, Some(config))
+            .await
+            .map_err(|e| format!("accountSubscribe: {}", e))
+    }
+
+    fn forward(&mut self, response: Self::Notification) {
+        match response.value.decode::<Account>() {
+            Some(account) => {
+                // so a resync after a reconnect only republishes what the stream missed
+                self.last_hash = Some(Some(hash(&account.data)));
+                publish(
+                    &self.app_state,
+                    self.address,
+                    response.context.slot,
+                    &account,
+                    &self.sender,
+                );
+            }
+            None => eprintln!("Undecodable account notification for {}", self.address),
+        }
+    }
+
+    async fn resync(&mut self) -> Result<(), String> {
+        self.refresh()
+            .await
+            .map_err(|e| format!("reading the account: {}", e.message()))
+    }
+
+    async fn poll(&mut self) {
+        if let Err(e) = self.refresh().await {
+            eprintln!("Error polling account {}: {}", self.address, e.message());
+            self.last_hash.get_or_insert(None);
+        }
+    }
+}
+
+async fn handle_socket(socket: WebSocket, app_state: Arc<AppState>, seeds: SatelliteSeeds) {
+    let (address, _bump) = seeds.pda(&app_state.program_id);
+    let (mut updates, upstream) = app_state.satellite_streams.subscribe(address);
+    if let Some(sender) = upstream {
+        let upstream = AccountUpstream {
+            app_state: app_state.clone(),
+            address,
+            sender,
+            last_hash: None,
+        };
+        tokio::spawn(run_upstream(app_state.clone(), upstream));
+    }
+    let (mut outgoing, mut incoming) = socket.split();
+
+    // start every client off with the current state, or why there is none yet
+    let snapshot = match fetch_satellite(&app_state, &seeds).await {
+        Ok(cached) => satellite_message(&cached),
+        Err(e) => error_message(&e),
+    };
+    if outgoing.send(Message::Text(snapshot)).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(message) => {
+                    if outgoing.send(Message::Text(message)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("Stream client for {} skipped {} updates", address, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // clients only ever send close frames; pings are answered by the socket itself
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    println!("Stream client for {} disconnected", address);
+}
+
+#[debug_handler]
+pub async fn stream_satellite(
+    SatellitePath(seeds): SatellitePath,
+    State(app_state): State<Arc<AppState>>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, ApiError> {
+    let ws = ws.map_err(|e| ApiError::UpgradeRequired {
+        protocol: "websocket",
+        reason: e.body_text(),
+    })?;
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, app_state, seeds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use axum::{routing::get, Router};
+    use serde_json::{json, Value};
+    use tokio::{net::TcpListener, time::timeout};
+
+    use super::*;
+    use crate::{
+        satellite::tests::{satellite_account, test_satellite},
+        AppConfig, InMemoryAccountSource, InMemoryFruitRepository, OperationStatus, RawConfig,
+        SatelliteCache, SatelliteEvents, EVENT_CHANNEL_CAPACITY, SATELLITE_CACHE_MAX_ENTRIES,
+    };
+
+    #[tokio::test]
+    async fn resync_publishes_changes_missed_while_unsubscribed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let source = Arc::new(InMemoryAccountSource::new());
+        let config = AppConfig::from_raw(RawConfig {
+            ws_url: Some(format!("ws://{}", listener.local_addr().unwrap())),
+            ..RawConfig::default()
+        })
+        .unwrap();
+        let program_id = config.program_id;
+        let owner = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        source.insert(
+            address,
+            satellite_account(
+                &program_id,
+                &test_satellite(owner, 25544, "ISS", OperationStatus::Active),
+            ),
+        );
+
+        // a stand-in pubsub node that drops the first subscription straight away and changes
+        // the account before acknowledging the next one, so no notification ever carries it
+        let connections = Arc::new(AtomicUsize::new(0));
+        let pubsub_source = source.clone();
+        let pubsub_node = Router::new().route(
+            "/",
+            get(move |ws: WebSocketUpgrade| async move {
+                ws.on_upgrade(move |mut socket| async move {
+                    let Some(Ok(Message::Text(request))) = socket.recv().await else {
+                        return;
+                    };
+                    let request: Value = serde_json::from_str(&request).unwrap();
+                    let first = connections.fetch_add(1, Ordering::SeqCst) == 0;
+                    if !first {
+                        pubsub_source.insert(
+                            address,
+                            satellite_account(
+                                &program_id,
+                                &test_satellite(owner, 25544, "ISS", OperationStatus::Offline),
+                            ),
+                        );
+                    }
+                    let reply = json!({ "jsonrpc": "2.0", "result": 1, "id": request["id"] });
+                    let _ = socket.send(Message::Text(reply.to_string().into())).await;
+                    if first {
+                        return;
+                    }
+                    while let Some(Ok(_)) = socket.recv().await {}
+                })
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, pubsub_node).await });
+
+        let app_state = Arc::new(AppState {
+            program_id,
+            account_source: source,
+            satellite_cache: SatelliteCache::new(config.cache_ttl, SATELLITE_CACHE_MAX_ENTRIES),
+            satellite_streams: SatelliteStreams::new(STREAM_CHANNEL_CAPACITY),
+            satellite_events: SatelliteEvents::new(EVENT_CHANNEL_CAPACITY),
+            fruits: Arc::new(InMemoryFruitRepository::with_default_fruits()),
+            config,
+        });
+        let (mut updates, sender) = app_state.satellite_streams.subscribe(address);
+        let upstream = AccountUpstream {
+            app_state: app_state.clone(),
+            address,
+            sender: sender.unwrap(),
+            last_hash: None,
+        };
+        tokio::spawn(run_upstream(app_state, upstream));
+
+        let update = timeout(Duration::from_secs(10), updates.recv())
+            .await
+            .expect("no update after the reconnect")
+            .unwrap();
+        let satellite: Value = serde_json::from_str(&update).unwrap();
+        assert_eq!(satellite["operation_status"], "Offline");
+    }
+}
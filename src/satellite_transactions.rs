@@ -0,0 +1,346 @@
+use std::{str::FromStr, sync::Arc};
+
+use axum::{debug_handler, extract::rejection::JsonRejection, extract::State, Json};
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::{
+    hash::{hash, Hash},
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use solana_sdk_ids::system_program;
+
+use crate::{
+    ApiError, AppState, ManeuverType, OperationStatus, SatellitePath, SatelliteSeeds,
+    SATELLITE_STRING_MAX_LEN,
+};
+
+// Anchor prefixes every instruction with the first 8 bytes of sha256("global:<name>")
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let digest = hash(format!("global:{}", name).as_bytes()).to_bytes();
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+// The editable fields of a satellite, i.e. SatelliteApiResponse without owner and mint_date
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SatelliteFields {
+    pub name: String,
+    pub country: String,
+    pub norad_id: u64,
+    pub launch_date: i64,
+    pub orbit_type: String,
+    pub inclination: f64,
+    pub altitude: f64,
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub raan: f64,
+    pub arg_of_periapsis: f64,
+    pub maneuver_type: ManeuverType,
+    pub operation_status: OperationStatus,
+}
+
+impl SatelliteFields {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        for (field, value) in [
+            ("name", &self.name),
+            ("country", &self.country),
+            ("orbit_type", &self.orbit_type),
+        ] {
+            if value.is_empty() {
+                return Err(ApiError::Validation {
+                    field,
+                    reason: "must not be empty".to_string(),
+                });
+            }
+            // the program allocates max_len bytes, not characters
+            if value.len() > SATELLITE_STRING_MAX_LEN {
+                return Err(ApiError::Validation {
+                    field,
+                    reason: format!(
+                        "is {} bytes long, the program allows at most {}",
+                        value.len(),
+                        SATELLITE_STRING_MAX_LEN
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+// Instruction arguments in the order the program's create_satellite handler declares them
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct CreateSatelliteArgs {
+    pub name: String,
+    pub country: String,
+    pub norad_id: u64,
+    pub launch_date: i64,
+    pub orbit_type: String,
+    pub inclination: f64,
+    pub altitude: f64,
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub raan: f64,
+    pub arg_of_periapsis: f64,
+    pub maneuver_type: ManeuverType,
+    pub operation_status: OperationStatus,
+}
+
+impl From<SatelliteFields> for CreateSatelliteArgs {
+    fn from(fields: SatelliteFields) -> Self {
+        CreateSatelliteArgs {
+            name: fields.name,
+            country: fields.country,
+            norad_id: fields.norad_id,
+            launch_date: fields.launch_date,
+            orbit_type: fields.orbit_type,
+            inclination: fields.inclination,
+            altitude: fields.altitude,
+            semi_major_axis: fields.semi_major_axis,
+            eccentricity: fields.eccentricity,
+            raan: fields.raan,
+            arg_of_periapsis: fields.arg_of_periapsis,
+            maneuver_type: fields.maneuver_type,
+            operation_status: fields.operation_status,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateSatelliteRequest {
+    pub user_authority: String,
+    pub registry_authority: String,
+    #[serde(flatten)]
+    pub satellite: SatelliteFields,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct UnsignedTransactionApiResponse {
+    pub instruction: &'static str,
+    pub satellite: String,
+    pub fee_payer: String,
+    pub recent_blockhash: String,
+    // bincode-serialized legacy Transaction with empty signatures, base64 encoded
+    pub transaction: String,
+}
+
+pub fn build_unsigned_transaction(
+    instruction: Instruction,
+    fee_payer: &Pubkey,
+    recent_blockhash: Hash,
+) -> Result<String, ApiError> {
+    let message = Message::new_with_blockhash(&[instruction], Some(fee_payer), &recent_blockhash);
+    let transaction = Transaction::new_unsigned(message);
+    let bytes = bincode::serialize(&transaction).map_err(|e| ApiError::Internal {
+        reason: format!("failed to serialize transaction: {}", e),
+    })?;
+    Ok(BASE64_STANDARD.encode(bytes))
+}
+
+// Match a variant name exactly as it is serialized, listing the valid ones on failure
+pub fn parse_variant<T: Copy + std::fmt::Debug>(
+    field: &'static str,
+    value: &str,
+    variants: &[T],
+) -> Result<T, ApiError> {
+    variants
+        .iter()
+        .copied()
+        .find(|variant| format!("{:?}", variant) == value)
+        .ok_or_else(|| ApiError::Validation {
+            field,
+            reason: format!(
+                "'{}' is not one of {}",
+                value,
+                variants
+                    .iter()
+                    .map(|variant| format!("{:?}", variant))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        })
+}
+
+// Accounts follow the program's CreateSatellite context:
+// satellite PDA (init), user authority (payer, signer), registry authority, system program
+pub fn create_satellite_instruction(
+    program_id: &Pubkey,
+    seeds: &SatelliteSeeds,
+    args: &CreateSatelliteArgs,
+) -> Result<(Instruction, Pubkey), ApiError> {
+    let (satellite_pda, _bump) = seeds.pda(program_id);
+
+    let mut data = instruction_discriminator("create_satellite").to_vec();
+    args.serialize(&mut data).map_err(|e| ApiError::Internal {
+        reason: format!("failed to encode create_satellite arguments: {}", e),
+    })?;
+
+    let instruction = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(satellite_pda, false),
+            AccountMeta::new(seeds.user_authority, true),
+            AccountMeta::new_readonly(seeds.registry_authority, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    };
+    Ok((instruction, satellite_pda))
+}
+
+// Accounts follow the program's UpdateSatellite context shared by update_status and
+// record_maneuver: satellite PDA (mut), user authority (signer)
+pub fn update_satellite_instruction<A: BorshSerialize>(
+    program_id: &Pubkey,
+    seeds: &SatelliteSeeds,
+    instruction_name: &str,
+    args: &A,
+) -> Result<(Instruction, Pubkey), ApiError> {
+    let (satellite_pda, _bump) = seeds.pda(program_id);
+
+    let mut data = instruction_discriminator(instruction_name).to_vec();
+    args.serialize(&mut data).map_err(|e| ApiError::Internal {
+        reason: format!("failed to encode {} arguments: {}", instruction_name, e),
+    })?;
+
+    let instruction = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(satellite_pda, false),
+            AccountMeta::new_readonly(seeds.user_authority, true),
+        ],
+        data,
+    };
+    Ok((instruction, satellite_pda))
+}
+
+pub(crate) fn parse_body_pubkey(field: &'static str, value: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(value).map_err(|e| ApiError::Validation {
+        field,
+        reason: format!("'{}' is not a valid Pubkey: {}", value, e),
+    })
+}
+
+pub(crate) async fn latest_blockhash(app_state: &AppState) -> Result<Hash, ApiError> {
+    app_state
+        .account_source
+        .get_latest_blockhash()
+        .await
+        .map_err(|e| {
+            eprintln!("Error fetching latest blockhash: {:?}", e);
+            ApiError::rpc(&e, None)
+        })
+}
+
+#[debug_handler]
+pub async fn create_satellite_transaction(
+    State(app_state): State<Arc<AppState>>,
+    body: Result<Json<CreateSatelliteRequest>, JsonRejection>,
+) -> Result<Json<UnsignedTransactionApiResponse>, ApiError> {
+    let Json(request) = body.map_err(|e| ApiError::InvalidBody {
+        reason: e.body_text(),
+    })?;
+    println!(
+        "Building create_satellite transaction for NORAD ID {}",
+        request.satellite.norad_id
+    );
+
+    request.satellite.validate()?;
+    let seeds = SatelliteSeeds {
+        user_authority: parse_body_pubkey("user_authority", &request.user_authority)?,
+        registry_authority: parse_body_pubkey("registry_authority", &request.registry_authority)?,
+        norad_id: request.satellite.norad_id,
+    };
+
+    let args = CreateSatelliteArgs::from(request.satellite);
+    let (instruction, satellite_pda) =
+        create_satellite_instruction(&app_state.program_id, &seeds, &args)?;
+
+    let recent_blockhash = latest_blockhash(&app_state).await?;
+    let transaction =
+        build_unsigned_transaction(instruction, &seeds.user_authority, recent_blockhash)?;
+
+    Ok(Json(UnsignedTransactionApiResponse {
+        instruction: "create_satellite",
+        satellite: satellite_pda.to_string(),
+        fee_payer: seeds.user_authority.to_string(),
+        recent_blockhash: recent_blockhash.to_string(),
+        transaction,
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UpdateStatusRequest {
+    pub operation_status: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RecordManeuverRequest {
+    pub maneuver_type: String,
+}
+
+async fn unsigned_update_transaction<A: BorshSerialize>(
+    app_state: &AppState,
+    seeds: &SatelliteSeeds,
+    instruction_name: &'static str,
+    args: &A,
+) -> Result<Json<UnsignedTransactionApiResponse>, ApiError> {
+    let (instruction, satellite_pda) =
+        update_satellite_instruction(&app_state.program_id, seeds, instruction_name, args)?;
+
+    let recent_blockhash = latest_blockhash(app_state).await?;
+    let transaction =
+        build_unsigned_transaction(instruction, &seeds.user_authority, recent_blockhash)?;
+
+    Ok(Json(UnsignedTransactionApiResponse {
+        instruction: instruction_name,
+        satellite: satellite_pda.to_string(),
+        fee_payer: seeds.user_authority.to_string(),
+        recent_blockhash: recent_blockhash.to_string(),
+        transaction,
+    }))
+}
+
+#[debug_handler]
+pub async fn update_status_transaction(
+    SatellitePath(seeds): SatellitePath,
+    State(app_state): State<Arc<AppState>>,
+    body: Result<Json<UpdateStatusRequest>, JsonRejection>,
+) -> Result<Json<UnsignedTransactionApiResponse>, ApiError> {
+    let Json(request) = body.map_err(|e| ApiError::InvalidBody {
+        reason: e.body_text(),
+    })?;
+    let operation_status = parse_variant(
+        "operation_status",
+        &request.operation_status,
+        &OperationStatus::ALL,
+    )?;
+    println!(
+        "Building update_status transaction for NORAD ID {} -> {:?}",
+        seeds.norad_id, operation_status
+    );
+
+    unsigned_update_transaction(&app_state, &seeds, "update_status", &operation_status).await
+}
+
+#[debug_handler]
+pub async fn record_maneuver_transaction(
+    SatellitePath(seeds): SatellitePath,
+    State(app_state): State<Arc<AppState>>,
+    body: Result<Json<RecordManeuverRequest>, JsonRejection>,
+) -> Result<Json<UnsignedTransactionApiResponse>, ApiError> {
+    let Json(request) = body.map_err(|e| ApiError::InvalidBody {
+        reason: e.body_text(),
+    })?;
+    let maneuver_type = parse_variant("maneuver_type", &request.maneuver_type, &ManeuverType::ALL)?;
+    println!(
+        "Building record_maneuver transaction for NORAD ID {} -> {:?}",
+        seeds.norad_id, maneuver_type
+    );
+
+    unsigned_update_transaction(&app_state, &seeds, "record_maneuver", &maneuver_type).await
+}
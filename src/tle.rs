@@ -0,0 +1,543 @@
+use std::{f64::consts::PI, ops::RangeInclusive};
+
+use axum::{debug_handler, extract::rejection::JsonRejection, Json};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use crate::{
+    ApiError, KeplerianElements, ManeuverType, OperationStatus, OrbitClass, SatelliteApiResponse,
+    SatelliteFields, EARTH_EQUATORIAL_RADIUS, EARTH_MU,
+};
+
+pub const TLE_LINE_LEN: usize = 69;
+// the largest catalog number the Alpha-5 scheme can encode (Z9999)
+pub const TLE_MAX_CATALOG_NUMBER: u64 = 339_999;
+// the years a two-digit epoch can name
+pub const TLE_EPOCH_YEARS: RangeInclusive<i32> = 1957..=2056;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+// Alpha-5 replaces the leading digit of catalog numbers above 99999 with a letter,
+// skipping I and O so they cannot be confused with 1 and 0
+const ALPHA5_LETTERS: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+// Modulo 10 sum of the digits, with each minus sign counting as 1
+pub fn tle_checksum(line: &str) -> u32 {
+    line.chars()
+        .map(|c| match c {
+            '-' => 1,
+            c => c.to_digit(10).unwrap_or(0),
+        })
+        .sum::<u32>()
+        % 10
+}
+
+fn encode_catalog_number(norad_id: u64) -> Result<String, ApiError> {
+    if norad_id <= 99_999 {
+        return Ok(format!("{:05}", norad_id));
+    }
+    let letter = ALPHA5_LETTERS
+        .chars()
+        .nth((norad_id / 10_000 - 10) as usize)
+        .filter(|_| norad_id <= TLE_MAX_CATALOG_NUMBER)
+        .ok_or_else(|| ApiError::Validation {
+            field: "norad_id",
+            reason: format!(
+                "{} does not fit in a TLE catalog number (at most {})",
+                norad_id, TLE_MAX_CATALOG_NUMBER
+            ),
+        })?;
+    Ok(format!("{}{:04}", letter, norad_id % 10_000))
+}
+
+fn decode_catalog_number(value: &str) -> Option<u64> {
+    let mut chars = value.chars();
+    let first = chars.next()?;
+    let rest = chars.as_str().parse::<u64>().ok()?;
+    match first.to_digit(10) {
+        Some(digit) => Some(digit as u64 * 10_000 + rest),
+        None => {
+            let index = ALPHA5_LETTERS.find(first)? as u64;
+            Some((index + 10) * 10_000 + rest)
+        }
+    }
+}
+
+// YYDDD.DDDDDDDD, day 1.0 being midnight on January 1st
+fn encode_epoch(epoch: &DateTime<Utc>) -> String {
+    let start_of_day = epoch.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default();
+    let day_fraction =
+        (epoch.naive_utc() - start_of_day).num_milliseconds() as f64 / 1_000.0 / SECONDS_PER_DAY;
+    format!(
+        "{:02}{:012.8}",
+        epoch.year() % 100,
+        epoch.ordinal() as f64 + day_fraction
+    )
+}
+
+fn decode_epoch(year: u32, day_of_year: f64) -> Option<f64> {
+    // two-digit years 57-99 are 1957-1999, the rest 2000-2056
+    let year = if year < 57 { 2000 + year } else { 1900 + year };
+    let start_of_year = NaiveDate::from_ymd_opt(year as i32, 1, 1)?
+        .and_hms_opt(0, 0, 0)?
+        .and_utc()
+        .timestamp();
+    Some(start_of_year as f64 + (day_of_year - 1.0) * SECONDS_PER_DAY)
+}
+
+fn with_checksum(line: String) -> String {
+    let checksum = tle_checksum(&line);
+    format!("{}{}", line, checksum)
+}
+
+// Render a three-line element set. The registry has no drag terms, international designator
+// or element set number, so those are written as zeros or blanks; mean anomaly is 0 at the
+// launch date, the same assumption the propagator makes.
+pub fn render_tle(satellite: &SatelliteApiResponse) -> Result<String, ApiError> {
+    let elements = KeplerianElements::from_satellite(satellite)?;
+    let catalog_number = encode_catalog_number(satellite.norad_id)?;
+    let epoch = DateTime::from_timestamp(elements.epoch, 0)
+        .filter(|epoch| TLE_EPOCH_YEARS.contains(&epoch.year()))
+        .ok_or_else(|| ApiError::Validation {
+            field: "launch_date",
+            reason: format!(
+                "{} is outside the years {}-{} a TLE epoch can express",
+                satellite.launch_date,
+                TLE_EPOCH_YEARS.start(),
+                TLE_EPOCH_YEARS.end()
+            ),
+        })?;
+    let mean_motion = elements.mean_motion() * SECONDS_PER_DAY / (2.0 * PI);
+
+    let line1 = with_checksum(format!(
+        "1 {}U {:<8} {}  .00000000  00000-0  00000-0 0  999",
+        catalog_number,
+        "",
+        encode_epoch(&epoch)
+    ));
+    let line2 = with_checksum(format!(
+        "2 {} {:8.4} {:8.4} {:07} {:8.4} {:8.4} {:11.8}{:5}",
+        catalog_number,
+        elements.inclination,
+        elements.raan.rem_euclid(360.0),
+        (elements.eccentricity * 1e7).round() as u64,
+        elements.arg_of_periapsis.rem_euclid(360.0),
+        elements.mean_anomaly.rem_euclid(360.0),
+        mean_motion,
+        0
+    ));
+    Ok(format!("{}\n{}\n{}\n", satellite.name, line1, line2))
+}
+
+// CCSDS OMM in the JSON key names CelesTrak and Space-Track use. The registry holds
+// osculating two-body elements in an inertial frame, not SGP4 mean elements in TEME, and the
+// message says so; SGP4 consumers should not treat it like a CelesTrak element set.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct OmmApiResponse {
+    pub object_name: String,
+    pub center_name: &'static str,
+    pub ref_frame: &'static str,
+    pub time_system: &'static str,
+    pub mean_element_theory: &'static str,
+    pub epoch: String,
+    pub mean_motion: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub ra_of_asc_node: f64,
+    pub arg_of_pericenter: f64,
+    pub mean_anomaly: f64,
+    pub ephemeris_type: u8,
+    pub classification_type: &'static str,
+    pub norad_cat_id: u64,
+    pub element_set_no: u32,
+    pub rev_at_epoch: u32,
+    pub bstar: f64,
+    pub mean_motion_dot: f64,
+    pub mean_motion_ddot: f64,
+}
+
+pub fn render_omm(satellite: &SatelliteApiResponse) -> Result<OmmApiResponse, ApiError> {
+    let elements = KeplerianElements::from_satellite(satellite)?;
+    let epoch = DateTime::from_timestamp(elements.epoch, 0).unwrap_or_default();
+    Ok(OmmApiResponse {
+        object_name: satellite.name.clone(),
+        center_name: "EARTH",
+        ref_frame: "EME2000",
+        time_system: "UTC",
+        mean_element_theory: "OSCULATING",
+        epoch: epoch.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+        mean_motion: elements.mean_motion() * SECONDS_PER_DAY / (2.0 * PI),
+        eccentricity: elements.eccentricity,
+        inclination: elements.inclination,
+        ra_of_asc_node: elements.raan.rem_euclid(360.0),
+        arg_of_pericenter: elements.arg_of_periapsis.rem_euclid(360.0),
+        mean_anomaly: elements.mean_anomaly,
+        ephemeris_type: 0,
+        classification_type: "U",
+        norad_cat_id: satellite.norad_id,
+        element_set_no: 999,
+        rev_at_epoch: 0,
+        bstar: 0.0,
+        mean_motion_dot: 0.0,
+        mean_motion_ddot: 0.0,
+    })
+}
+
+// The orbital part of a parsed element set
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedTle {
+    pub name: Option<String>,
+    pub norad_id: u64,
+    // unix seconds
+    pub epoch: f64,
+    // degrees
+    pub inclination: f64,
+    pub raan: f64,
+    pub eccentricity: f64,
+    pub arg_of_periapsis: f64,
+    pub mean_anomaly: f64,
+    // revolutions per day
+    pub mean_motion: f64,
+}
+
+fn invalid_tle(reason: String) -> ApiError {
+    ApiError::Validation {
+        field: "tle",
+        reason,
+    }
+}
+
+fn tle_field<T: std::str::FromStr>(
+    line: &str,
+    line_number: u8,
+    columns: std::ops::Range<usize>,
+    name: &str,
+) -> Result<T, ApiError> {
+    let value = line.get(columns.clone()).unwrap_or_default().trim();
+    value.parse().map_err(|_| {
+        invalid_tle(format!(
+            "line {} columns {}-{}: {} '{}' is not a number",
+            line_number,
+            columns.start + 1,
+            columns.end,
+            name,
+            value
+        ))
+    })
+}
+
+// Parse a two-line element set, optionally preceded by a title line
+pub fn parse_tle(text: &str) -> Result<ParsedTle, ApiError> {
+    let lines = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let (name, line1, line2) = match lines.as_slice() {
+        [line1, line2] => (None, *line1, *line2),
+        [name, line1, line2] => {
+            // some sources prefix the title line with "0 "
+            let name = name.strip_prefix("0 ").unwrap_or(name).trim();
+            (Some(name.to_string()), *line1, *line2)
+        }
+        _ => {
+            return Err(invalid_tle(format!(
+                "expected 2 or 3 non-empty lines, got {}",
+                lines.len()
+            )))
+        }
+    };
+
+    for (line_number, line) in [(1u8, line1), (2, line2)] {
+        if !line.is_ascii() || line.len() != TLE_LINE_LEN {
+            return Err(invalid_tle(format!(
+                "line {} must be {} ASCII characters, got {}",
+                line_number,
+                TLE_LINE_LEN,
+                line.chars().count()
+            )));
+        }
+        if !line.starts_with(&format!("{} ", line_number)) {
+            return Err(invalid_tle(format!(
+                "line {} must start with '{} '",
+                line_number, line_number
+            )));
+        }
+        let expected = tle_checksum(&line[..TLE_LINE_LEN - 1]);
+        let actual = line[TLE_LINE_LEN - 1..].parse::<u32>().ok();
+        if actual != Some(expected) {
+            return Err(invalid_tle(format!(
+                "line {} checksum is '{}', expected {}",
+                line_number,
+                &line[TLE_LINE_LEN - 1..],
+                expected
+            )));
+        }
+    }
+
+    let norad_id = decode_catalog_number(&line1[2..7])
+        .ok_or_else(|| invalid_tle(format!("'{}' is not a catalog number", &line1[2..7])))?;
+    if decode_catalog_number(&line2[2..7]) != Some(norad_id) {
+        return Err(invalid_tle(format!(
+            "catalog numbers differ between line 1 ('{}') and line 2 ('{}')",
+            &line1[2..7],
+            &line2[2..7]
+        )));
+    }
+
+    let year = tle_field(line1, 1, 18..20, "epoch year")?;
+    let day_of_year = tle_field(line1, 1, 20..32, "epoch day")?;
+    let epoch = decode_epoch(year, day_of_year)
+        .ok_or_else(|| invalid_tle(format!("epoch {}{} is not a date", year, day_of_year)))?;
+    // a day of year past the end of 2056 or before 1957 would land outside the two-digit years
+    if !DateTime::from_timestamp(epoch.floor() as i64, 0)
+        .is_some_and(|epoch| TLE_EPOCH_YEARS.contains(&epoch.year()))
+    {
+        return Err(invalid_tle(format!(
+            "epoch day {} of year {:02} falls outside {}-{}",
+            day_of_year,
+            year,
+            TLE_EPOCH_YEARS.start(),
+            TLE_EPOCH_YEARS.end()
+        )));
+    }
+    let eccentricity: u32 = tle_field(line2, 2, 26..33, "eccentricity")?;
+
+    Ok(ParsedTle {
+        name,
+        norad_id,
+        epoch,
+        inclination: tle_field(line2, 2, 8..16, "inclination")?,
+        raan: tle_field(line2, 2, 17..25, "right ascension of the ascending node")?,
+        eccentricity: eccentricity as f64 * 1e-7,
+        arg_of_periapsis: tle_field(line2, 2, 34..42, "argument of perigee")?,
+        mean_anomaly: tle_field(line2, 2, 43..51, "mean anomaly")?,
+        mean_motion: tle_field(line2, 2, 52..63, "mean motion")?,
+    })
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TleImportRequest {
+    pub tle: String,
+    // everything a TLE does not carry
+    pub country: String,
+    pub name: Option<String>,
+    pub maneuver_type: Option<ManeuverType>,
+    pub operation_status: Option<OperationStatus>,
+}
+
+impl ParsedTle {
+    // The registry has no mean anomaly field, so launch_date is moved back to the last
+    // perigee passage before the epoch; propagating from it reproduces the element set.
+    pub fn into_fields(
+        self,
+        country: String,
+        name: Option<String>,
+        maneuver_type: ManeuverType,
+        operation_status: OperationStatus,
+    ) -> Result<SatelliteFields, ApiError> {
+        if self.mean_motion <= 0.0 {
+            return Err(invalid_tle(format!(
+                "mean motion {} must be positive",
+                self.mean_motion
+            )));
+        }
+        let mean_motion = self.mean_motion * 2.0 * PI / SECONDS_PER_DAY;
+        let semi_major_axis = (EARTH_MU / (mean_motion * mean_motion)).cbrt();
+        let launch_date = self.epoch - self.mean_anomaly.to_radians() / mean_motion;
+
+        let name = name.or(self.name).ok_or_else(|| ApiError::Validation {
+            field: "name",
+            reason: "the TLE has no title line, so a name must be given".to_string(),
+        })?;
+        Ok(SatelliteFields {
+            name,
+            country,
+            norad_id: self.norad_id,
+            launch_date: launch_date.round() as i64,
+            orbit_type: OrbitClass::classify(semi_major_axis, self.eccentricity)
+                .as_str()
+                .to_string(),
+            inclination: self.inclination,
+            altitude: semi_major_axis - EARTH_EQUATORIAL_RADIUS,
+            semi_major_axis,
+            eccentricity: self.eccentricity,
+            raan: self.raan,
+            arg_of_periapsis: self.arg_of_periapsis,
+            maneuver_type,
+            operation_status,
+        })
+    }
+}
+
+#[debug_handler]
+pub async fn import_tle(
+    body: Result<Json<TleImportRequest>, JsonRejection>,
+) -> Result<Json<SatelliteFields>, ApiError> {
+    let Json(request) = body.map_err(|e| ApiError::InvalidBody {
+        reason: e.body_text(),
+    })?;
+
+    let parsed = parse_tle(&request.tle)?;
+    println!("Parsed TLE for NORAD ID {}", parsed.norad_id);
+    let fields = parsed.into_fields(
+        request.country,
+        request.name,
+        request
+            .maneuver_type
+            .unwrap_or(ManeuverType::StationKeeping),
+        request.operation_status.unwrap_or(OperationStatus::Active),
+    )?;
+    fields.validate()?;
+    Ok(Json(fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::satellite::tests::test_satellite;
+
+    // the ISS element set from the NORAD two-line element set format description
+    const ISS_TLE: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    // The shared test satellite moved onto the ISS elements above
+    fn satellite(norad_id: u64) -> SatelliteApiResponse {
+        SatelliteApiResponse {
+            inclination: 51.6416,
+            altitude: 420.0,
+            semi_major_axis: EARTH_EQUATORIAL_RADIUS + 420.0,
+            eccentricity: 0.0006703,
+            raan: 247.4627,
+            arg_of_periapsis: 130.536,
+            ..SatelliteApiResponse::from(test_satellite(
+                Pubkey::new_unique(),
+                norad_id,
+                "ISS",
+                OperationStatus::Active,
+            ))
+        }
+    }
+
+    #[test]
+    fn parses_published_iss_tle() {
+        let parsed = parse_tle(ISS_TLE).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("ISS (ZARYA)"));
+        assert_eq!(parsed.norad_id, 25544);
+        // 2008-09-20T12:25:40.104Z
+        assert!((parsed.epoch - 1_221_913_540.104).abs() < 1e-3);
+        assert_eq!(parsed.inclination, 51.6416);
+        assert_eq!(parsed.raan, 247.4627);
+        assert!((parsed.eccentricity - 0.0006703).abs() < 1e-12);
+        assert_eq!(parsed.arg_of_periapsis, 130.5360);
+        assert_eq!(parsed.mean_anomaly, 325.0288);
+        assert_eq!(parsed.mean_motion, 15.72125391);
+
+        // the title line is optional
+        let without_name = ISS_TLE.lines().skip(1).collect::<Vec<_>>().join("\n");
+        assert_eq!(parse_tle(&without_name).unwrap().name, None);
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        assert_eq!(tle_checksum("1 -0-"), 3);
+        let corrupted = ISS_TLE.replace(" 0  2927", " 0  2928");
+        let error = parse_tle(&corrupted).unwrap_err();
+        assert!(error.message().contains("line 1 checksum"));
+        // a changed digit is caught even though the checksum itself is intact
+        let corrupted = ISS_TLE.replace("51.6416", "51.6417");
+        let error = parse_tle(&corrupted).unwrap_err();
+        assert!(error.message().contains("line 2 checksum"));
+    }
+
+    #[test]
+    fn alpha5_catalog_numbers() {
+        assert_eq!(decode_catalog_number("A0000"), Some(100_000));
+        assert_eq!(decode_catalog_number("J1234"), Some(181_234));
+        assert_eq!(decode_catalog_number("Z9999"), Some(TLE_MAX_CATALOG_NUMBER));
+        assert_eq!(decode_catalog_number("25544"), Some(25_544));
+        // I and O are never used
+        assert_eq!(decode_catalog_number("I0000"), None);
+        assert_eq!(encode_catalog_number(100_000).unwrap(), "A0000");
+        assert_eq!(encode_catalog_number(99_999).unwrap(), "99999");
+        assert_eq!(encode_catalog_number(181_234).unwrap(), "J1234");
+        assert!(encode_catalog_number(TLE_MAX_CATALOG_NUMBER + 1).is_err());
+
+        let parsed = parse_tle(&render_tle(&satellite(100_000)).unwrap()).unwrap();
+        assert_eq!(parsed.norad_id, 100_000);
+    }
+
+    #[test]
+    fn render_parse_round_trip() {
+        let satellite = satellite(25544);
+        let text = render_tle(&satellite).unwrap();
+        let parsed = parse_tle(&text).unwrap();
+        let elements = KeplerianElements::from_satellite(&satellite).unwrap();
+
+        assert_eq!(parsed.name.as_deref(), Some("ISS"));
+        assert_eq!(parsed.norad_id, satellite.norad_id);
+        assert!((parsed.epoch - satellite.launch_date as f64).abs() < 1e-3);
+        assert!((parsed.inclination - satellite.inclination).abs() < 1e-4);
+        assert!((parsed.raan - satellite.raan).abs() < 1e-4);
+        assert!((parsed.eccentricity - satellite.eccentricity).abs() < 1e-7);
+        assert!((parsed.arg_of_periapsis - satellite.arg_of_periapsis).abs() < 1e-4);
+        assert_eq!(parsed.mean_anomaly, 0.0);
+        let mean_motion = elements.mean_motion() * SECONDS_PER_DAY / (2.0 * PI);
+        assert!((parsed.mean_motion - mean_motion).abs() < 1e-8);
+
+        // and back into the fields the registry stores
+        let fields = parsed
+            .into_fields(
+                "US".to_string(),
+                None,
+                ManeuverType::StationKeeping,
+                OperationStatus::Active,
+            )
+            .unwrap();
+        assert!((fields.semi_major_axis - satellite.semi_major_axis).abs() < 1e-3);
+        assert_eq!(fields.launch_date, satellite.launch_date);
+    }
+
+    #[test]
+    fn epochs_outside_two_digit_years_are_rejected() {
+        let at = |date: &str| {
+            let launch_date = DateTime::parse_from_rfc3339(date).unwrap().timestamp();
+            render_tle(&SatelliteApiResponse {
+                launch_date,
+                ..satellite(25544)
+            })
+        };
+        assert!(at("1957-01-01T00:00:00Z").is_ok());
+        assert!(at("2056-12-31T23:59:59Z").is_ok());
+        for date in ["1956-12-31T23:59:59Z", "2057-01-01T00:00:00Z"] {
+            let error = at(date).unwrap_err();
+            assert!(matches!(
+                error,
+                ApiError::Validation {
+                    field: "launch_date",
+                    ..
+                }
+            ));
+        }
+
+        // day 367 of 2056 is already in 2057; a day before the 1st would be 1956
+        for epoch in ["56367.00000000", "57000.50000000"] {
+            let line1 = format!(
+                "1 25544U 98067A   {} -.00002182  00000-0 -11606-4 0  292",
+                epoch
+            );
+            let tle = format!(
+                "{}{}\n{}",
+                line1,
+                tle_checksum(&line1),
+                ISS_TLE.lines().nth(2).unwrap()
+            );
+            let error = parse_tle(&tle).unwrap_err();
+            assert!(error.message().contains("falls outside 1957-2056"));
+        }
+    }
+}
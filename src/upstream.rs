@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::{future::BoxFuture, stream::BoxStream, StreamExt};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use tokio::{
+    sync::broadcast,
+    time::{interval_at, sleep, Instant},
+};
+
+use crate::{AccountSourceKind, AppState};
+
+pub const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+pub const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+// how often an upstream subscription checks whether anyone is still listening
+pub const STREAM_IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// the in-memory account source has no pubsub, so its accounts are polled instead
+pub const MEMORY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// A notification stream plus the call that ends it, as handed back by the pubsub client
+pub type Subscription<'a, T> = (
+    BoxStream<'a, T>,
+    Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>,
+);
+
+// One broadcast channel per key, fed by a single upstream subscription and shared by every
+// client listening on that key
+#[derive(Debug)]
+pub struct FanOut<K, T> {
+    capacity: usize,
+    channels: Mutex<HashMap<K, broadcast::Sender<T>>>,
+}
+
+impl<K: Eq + Hash, T: Clone> FanOut<K, T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Join the channel for a key; the sender comes back when the caller has to start the
+    // upstream subscription because nobody was listening yet
+    pub fn subscribe(&self, key: K) -> (broadcast::Receiver<T>, Option<broadcast::Sender<T>>) {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get(&key) {
+            Some(sender) => (sender.subscribe(), None),
+            None => {
+                let (sender, receiver) = broadcast::channel(self.capacity);
+                channels.insert(key, sender.clone());
+                (receiver, Some(sender))
+            }
+        }
+    }
+
+    // Drop the channel once its last client has gone; taken under the same lock as
+    // subscribe so a client joining at that moment is never left without an upstream
+    pub fn release_if_idle(&self, key: &K, sender: &broadcast::Sender<T>) -> bool {
+        let mut channels = self.channels.lock().unwrap();
+        if sender.receiver_count() > 0 {
+            return false;
+        }
+        if channels
+            .get(key)
+            .is_some_and(|current| current.same_channel(sender))
+        {
+            channels.remove(key);
+        }
+        true
+    }
+}
+
+// What one kind of upstream subscribes to and how its notifications reach the fan-out
+#[async_trait]
+pub trait Upstream: Send {
+    type Notification: Send;
+
+    // what the subscription is for, in log lines, e.g. "account <address>"
+    fn describe(&self) -> String;
+
+    // true once nobody is listening and the upstream should stop
+    fn release_if_idle(&self) -> bool;
+
+    async fn subscribe<'a>(
+        &self,
+        client: &'a PubsubClient,
+    ) -> Result<Subscription<'a, Self::Notification>, String>;
+
+    // Runs each time the subscription comes up, to pick up whatever changed while there was
+    // none
+    async fn resync(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn forward(&mut self, notification: Self::Notification);
+
+    // One round of reading the account source directly, for when there is no pubsub
+    async fn poll(&mut self);
+}
+
+// Forward notifications until the subscription drops or goes idle.
+// Returns true when the upstream should stop for good.
+async fn forward_notifications<U: Upstream>(
+    upstream: &mut U,
+    client: &PubsubClient,
+) -> Result<bool, String> {
+    let (mut notifications, unsubscribe) = upstream.subscribe(client).await?;
+    println!("Subscribed to {}", upstream.describe());
+    upstream.resync().await?;
+
+    // created once so a busy subscription can't keep pushing the next check back
+    let mut idle_check = interval_at(
+        Instant::now() + STREAM_IDLE_CHECK_INTERVAL,
+        STREAM_IDLE_CHECK_INTERVAL,
+    );
+    let stop = loop {
+        tokio::select! {
+            notification = notifications.next() => match notification {
+                Some(notification) => upstream.forward(notification),
+                None => break false,
+            },
+            _ = idle_check.tick() => {
+                if upstream.release_if_idle() {
+                    break true;
+                }
+            }
+        }
+    };
+    if stop {
+        unsubscribe().await;
+    }
+    Ok(stop)
+}
+
+async fn connect_and_forward<U: Upstream>(upstream: &mut U, ws_url: &str) -> Result<bool, String> {
+    let client = PubsubClient::new(ws_url)
+        .await
+        .map_err(|e| format!("connecting to {}: {}", ws_url, e))?;
+    let result = forward_notifications(upstream, &client).await;
+    if let Err(e) = client.shutdown().await {
+        eprintln!(
+            "Error shutting down pubsub client for {}: {}",
+            upstream.describe(),
+            e
+        );
+    }
+    result
+}
+
+// Keeps one upstream alive for as long as clients are listening, reconnecting with
+// exponential backoff when it drops
+pub async fn run_upstream<U: Upstream>(app_state: Arc<AppState>, mut upstream: U) {
+    if app_state.config.account_source == AccountSourceKind::Memory {
+        loop {
+            upstream.poll().await;
+            sleep(MEMORY_POLL_INTERVAL).await;
+            if upstream.release_if_idle() {
+                break;
+            }
+        }
+        println!("Stopped polling {}", upstream.describe());
+        return;
+    }
+
+    let mut delay = RECONNECT_MIN_DELAY;
+    loop {
+        match connect_and_forward(&mut upstream, &app_state.config.ws_url).await {
+            Ok(true) => break,
+            // it was up, so start the backoff over
+            Ok(false) => {
+                eprintln!(
+                    "Subscription to {} dropped, reconnecting",
+                    upstream.describe()
+                );
+                delay = RECONNECT_MIN_DELAY;
+            }
+            Err(e) => eprintln!(
+                "Subscription to {} failed: {}; retrying in {:?}",
+                upstream.describe(),
+                e,
+                delay
+            ),
+        }
+        sleep(delay).await;
+        if upstream.release_if_idle() {
+            break;
+        }
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+    }
+    println!("Unsubscribed from {}", upstream.describe());
+}